@@ -0,0 +1,124 @@
+/*
+ * Instruction builders for clients, they serialize the CrowdfundingInstruction
+ * and list the accounts in the order the program reads them.
+ * Only compiled with the "client" feature so the on-chain program doesn't carry them.
+ */
+use crate::{
+    admin_registry_pda, campaign_pda, global_state_pda, platform, treasury_pda, CampaignDetails, CrowdfundingInstruction,
+    DonateRequest, WithdrawRequest,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/*----------------------------*/
+/*
+ * Create "campaign", its writing_account is the campaign_pda of the admin and name,
+ * the admin signs and pays the rent of it, of its admin registry and of the global state when it is the first,
+ * plus the CREATION_FEE_LAMPORTS sent to the treasury.
+ */
+pub fn create_campaign_ix(program_id: &Pubkey, campaign: CampaignDetails) -> Instruction {
+    let (campaign_address, _) = campaign_pda(program_id, &campaign.admin, &campaign.name);
+    let (registry_address, _) = admin_registry_pda(program_id, &campaign.admin);
+    let (global_address, _) = global_state_pda(program_id);
+    let (treasury_address, _) = treasury_pda(program_id);
+    let admin = campaign.admin;
+    Instruction::new_with_borsh(
+        *program_id,
+        &CrowdfundingInstruction::CreateCampaign(Box::new(campaign)),
+        vec![
+            AccountMeta::new(campaign_address, false),
+            AccountMeta::new(admin, true),
+            AccountMeta::new(registry_address, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(global_address, false),
+            AccountMeta::new(treasury_address, false),
+        ],
+    )
+}
+
+/*----------------------------*/
+/*
+ * Withdraw "amount" lamports of the campaign at "campaign_address" to its beneficiary,
+ * the platform fee is taken out of it.
+ */
+pub fn withdraw_ix(
+    program_id: &Pubkey,
+    campaign_address: &Pubkey,
+    admin: &Pubkey,
+    beneficiary: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &CrowdfundingInstruction::Withdraw(WithdrawRequest { amount }),
+        vec![
+            AccountMeta::new(*campaign_address, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(platform::id(), false),
+            AccountMeta::new(*beneficiary, false),
+        ],
+    )
+}
+
+/*----------------------------*/
+/*
+ * Donate every lamport of "donator_program_account", a program owned account without data
+ * the donor created and funded earlier in the same transaction.
+ * Campaigns with a matcher also need its matcher_program_account (see "matcher_pda") appended as a writable account,
+ * followed by the system program when a campaign with a pool fee has no match pool yet.
+ */
+pub fn donate_ix(
+    program_id: &Pubkey,
+    campaign_address: &Pubkey,
+    donator_program_account: &Pubkey,
+    donor: &Pubkey,
+    anonymous: bool,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &CrowdfundingInstruction::Donate(DonateRequest { donor: *donor, anonymous }),
+        vec![
+            AccountMeta::new(*campaign_address, false),
+            AccountMeta::new(*donator_program_account, false),
+            AccountMeta::new_readonly(*donor, true),
+        ],
+    )
+}
+
+/**********************************************************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+
+    #[test]
+    fn donate_ix_is_understood_by_the_program() {
+        let program_id = Pubkey::new_unique();
+        let donor = Pubkey::new_unique();
+        let instruction = donate_ix(&program_id, &Pubkey::new_unique(), &Pubkey::new_unique(), &donor, false);
+        match CrowdfundingInstruction::try_from_slice(&instruction.data).unwrap() {
+            CrowdfundingInstruction::Donate(request) => {
+                assert_eq!(request.donor, donor);
+                assert!(!request.anonymous);
+            }
+            instruction => panic!("unexpected instruction {:?}", instruction),
+        }
+        assert!(instruction.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn withdraw_ix_sends_the_fee_to_the_platform() {
+        let instruction = withdraw_ix(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1_000,
+        );
+        assert_eq!(instruction.data, [&[1][..], &1_000u64.to_le_bytes()].concat());
+        assert_eq!(instruction.accounts[2].pubkey, platform::id());
+    }
+}
@@ -1,55 +1,556 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
-    pubkey::Pubkey,
-    rent::Rent,
-    sysvar::Sysvar,
+    program_pack::Pack,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    rent::{Rent, ACCOUNT_STORAGE_OVERHEAD},
+    system_instruction, system_program,
+    sysvar::{self, Sysvar},
 };
+use thiserror::Error;
+
+#[cfg(feature = "client")]
+pub mod instruction;
 
 /**********************************************************************/
-fn process_instruction(
+/*
+ * Every instruction sent to the program is a Borsh serialized "CrowdfundingInstruction",
+ * the first byte is the variant index (0, 1, 2, ...) and the rest is the payload of that variant.
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+enum CrowdfundingInstruction {
+    /*
+     * boxed, the campaign is far bigger than the payload of every other variant.
+     */
+    CreateCampaign(Box<CampaignDetails>),
+    Withdraw(WithdrawRequest),
+    Donate(DonateRequest),
+    UpdateCampaign(UpdateRequest),
+    Refund(RefundRequest),
+    CloseCampaign,
+    TransferAdmin(TransferAdminRequest),
+    Migrate,
+    EndCampaign,
+    DonateSpl(DonateSplRequest),
+    FundMatchPool,
+    Pause,
+    Unpause,
+    CreateCampaignMinimal(MinimalCampaignRequest),
+    FlagCampaign,
+    UnflagCampaign,
+    DonateSplit(DonateSplitRequest),
+    WithdrawAll,
+    WithdrawSplit(WithdrawSplitRequest),
+    CanWithdraw(WithdrawRequest),
+    ScheduleWithdrawal(ScheduleWithdrawalRequest),
+    ExecuteScheduledWithdrawal,
+    SetMetadata(SetMetadataRequest),
+    EnableRefunds,
+    ExtendDeadline(ExtendDeadlineRequest),
+    DonateDirect(DonateDirectRequest),
+    Reconcile,
+    SetTarget(SetTargetRequest),
+    DonateWithReceipt(DonateRequest),
+    RequestWithdraw(WithdrawRequest),
+    ConfirmWithdraw,
+    CancelWithdraw,
+    DonateWithMessage(DonateWithMessageRequest),
+    ForkFunds(ForkFundsRequest),
+    SweepWrongMint,
+    IsAdmin,
+    WithdrawSurplus,
+    WithdrawSpl(WithdrawRequest),
+}
+
+/*
+ * Errors specific to this program, they reach the client as "ProgramError::Custom(code)"
+ * where code is the position of the variant in this enum.
+ */
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum CrowdfundingError {
+    #[error("A required account did not sign the transaction")]
+    NotSigner,
+    #[error("The signer is not the admin of the campaign")]
+    WrongAdmin,
+    #[error("Not enough lamports to keep the account rent exempt")]
+    InsufficientFundsForRent,
+    #[error("The campaign deadline has passed")]
+    CampaignExpired,
+    #[error("The campaign data does not fit in the account")]
+    AccountDataTooSmall,
+    #[error("The campaign deadline has not passed yet")]
+    CampaignStillActive,
+    #[error("The donator_program_account holds no lamports")]
+    NothingToDonate,
+    #[error("The donation is below the campaign minimum")]
+    DonationTooSmall,
+    #[error("The campaign is closed")]
+    CampaignClosed,
+    #[error("The campaign did not reach its target amount")]
+    GoalNotReached,
+    #[error("The token account is not for the mint of the campaign")]
+    WrongMint,
+    #[error("The campaign is paused")]
+    CampaignPaused,
+    #[error("The withdrawal exceeds the daily withdrawal cap")]
+    DailyWithdrawLimitExceeded,
+    #[error("The campaign is flagged as fraudulent")]
+    CampaignFlagged,
+    #[error("The image link is not an http, https or ipfs URL")]
+    InvalidImageLink,
+    #[error("The admin reached the maximum number of campaigns")]
+    TooManyCampaigns,
+    #[error("The scheduled withdrawal is not due yet")]
+    ScheduledWithdrawalNotDue,
+    #[error("The campaign reached its goal")]
+    GoalReached,
+    #[error("Not enough of the campaign signers signed the withdrawal")]
+    NotEnoughSigners,
+    #[error("The admin can't donate to this campaign")]
+    SelfDonation,
+    #[error("The donation exceeds the maximum per donor of the campaign")]
+    DonorLimitExceeded,
+    #[error("The campaign still holds lamports to withdraw")]
+    FundsRemaining,
+    #[error("The withdrawal is above the time lock threshold, request it first")]
+    WithdrawalTimeLocked,
+    #[error("Only a failed all-or-nothing campaign can refund its donors")]
+    RefundsNotAllowed,
+}
+
+impl From<CrowdfundingError> for ProgramError {
+    fn from(e: CrowdfundingError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+/*----------------------------*/
+pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    if instruction_data.len() == 0 {
+    let (tag, payload) = instruction_data.split_first().ok_or_else(|| {
+        msg!("Empty instruction data!!!");
+        ProgramError::InvalidInstructionData
+    })?;
+    validate_instruction_len(*tag, payload)?;
+    let instruction = CrowdfundingInstruction::try_from_slice(instruction_data)
+    .map_err(|_| {
+        msg!("No entry_point found!!!");
+        ProgramError::InvalidInstructionData
+    })?;
+
+    match instruction {
+        CrowdfundingInstruction::CreateCampaign(input_data) => {
+            create_campain(program_id, accounts, *input_data)
+        }
+        CrowdfundingInstruction::Withdraw(input_data) => {
+            withdraw(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::Donate(input_data) => {
+            donate(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::UpdateCampaign(input_data) => {
+            update_campain(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::Refund(input_data) => {
+            refund(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::CloseCampaign => {
+            close_campaign(program_id, accounts)
+        }
+        CrowdfundingInstruction::TransferAdmin(input_data) => {
+            transfer_admin(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::Migrate => {
+            migrate(program_id, accounts)
+        }
+        CrowdfundingInstruction::EndCampaign => {
+            end_campaign(program_id, accounts)
+        }
+        CrowdfundingInstruction::DonateSpl(input_data) => {
+            donate_spl(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::FundMatchPool => {
+            fund_match_pool(program_id, accounts)
+        }
+        CrowdfundingInstruction::Pause => {
+            set_paused(program_id, accounts, true)
+        }
+        CrowdfundingInstruction::Unpause => {
+            set_paused(program_id, accounts, false)
+        }
+        CrowdfundingInstruction::CreateCampaignMinimal(input_data) => {
+            create_campain_minimal(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::FlagCampaign => {
+            set_flagged(program_id, accounts, true)
+        }
+        CrowdfundingInstruction::UnflagCampaign => {
+            set_flagged(program_id, accounts, false)
+        }
+        CrowdfundingInstruction::DonateSplit(input_data) => {
+            donate_split(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::WithdrawAll => {
+            withdraw_all(program_id, accounts)
+        }
+        CrowdfundingInstruction::WithdrawSplit(input_data) => {
+            withdraw_split(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::CanWithdraw(input_data) => {
+            can_withdraw(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::ScheduleWithdrawal(input_data) => {
+            schedule_withdrawal(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::ExecuteScheduledWithdrawal => {
+            execute_scheduled_withdrawal(program_id, accounts)
+        }
+        CrowdfundingInstruction::SetMetadata(input_data) => {
+            set_metadata(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::EnableRefunds => {
+            enable_refunds(program_id, accounts)
+        }
+        CrowdfundingInstruction::ExtendDeadline(input_data) => {
+            extend_deadline(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::DonateDirect(input_data) => {
+            donate_direct(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::Reconcile => {
+            reconcile(program_id, accounts)
+        }
+        CrowdfundingInstruction::SetTarget(input_data) => {
+            set_target(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::DonateWithReceipt(input_data) => {
+            donate_with_receipt(program_id, accounts, input_data, String::new())
+        }
+        CrowdfundingInstruction::RequestWithdraw(input_data) => {
+            request_withdraw(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::ConfirmWithdraw => {
+            confirm_withdraw(program_id, accounts)
+        }
+        CrowdfundingInstruction::CancelWithdraw => {
+            cancel_withdraw(program_id, accounts)
+        }
+        CrowdfundingInstruction::DonateWithMessage(input_data) => {
+            donate_with_message(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::ForkFunds(input_data) => {
+            fork_funds(program_id, accounts, input_data)
+        }
+        CrowdfundingInstruction::SweepWrongMint => {
+            sweep_wrong_mint(program_id, accounts)
+        }
+        CrowdfundingInstruction::IsAdmin => {
+            is_admin(program_id, accounts)
+        }
+        CrowdfundingInstruction::WithdrawSurplus => {
+            withdraw_surplus(program_id, accounts)
+        }
+        CrowdfundingInstruction::WithdrawSpl(input_data) => {
+            withdraw_spl(program_id, accounts, input_data)
+        }
+    }
+}
+
+/*
+ * Programs depending on this crate (to call load_campaign, the instruction builders, ...)
+ * build it with "no-entrypoint" so they don't get a second entrypoint symbol.
+ */
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/*----------------------------*/
+/*
+ * Reject a payload shorter than the smallest one of the instruction "tag" (its variant index),
+ * so truncated instruction data gets a clear log before deserializing.
+ * Strings and vectors count for their 4 bytes length, a missing Donate anonymous flag is allowed.
+ */
+pub fn validate_instruction_len(tag: u8, data: &[u8]) -> ProgramResult {
+    let min_len = match tag {
+        0 => campaign_account_size() - 2 * MAX_NAME_LEN - MAX_DESCRIPTION_LEN - MAX_IMAGE_LINK_LEN - 32 * MAX_SIGNERS, // CreateCampaign
+        1 => 8,                         // Withdraw
+        2 => 32,                        // Donate
+        3 => 4 + 4 + 4,                 // UpdateCampaign
+        4 => 8,                         // Refund
+        5 => 0,                         // CloseCampaign
+        6 => 32,                        // TransferAdmin
+        7 => 0,                         // Migrate
+        8 => 0,                         // EndCampaign
+        9 => 8,                         // DonateSpl
+        10 => 0,                        // FundMatchPool
+        11 => 0,                        // Pause
+        12 => 0,                        // Unpause
+        13 => 4 + 32,                   // CreateCampaignMinimal
+        14 => 0,                        // FlagCampaign
+        15 => 0,                        // UnflagCampaign
+        16 => 4,                        // DonateSplit
+        17 => 0,                        // WithdrawAll
+        18 => 4,                        // WithdrawSplit
+        19 => 8,                        // CanWithdraw
+        20 => 8 + 8 + 8,                // ScheduleWithdrawal
+        21 => 0,                        // ExecuteScheduledWithdrawal
+        22 => 4 + 4,                    // SetMetadata
+        23 => 0,                        // EnableRefunds
+        24 => 8,                        // ExtendDeadline
+        25 => 8 + 1,                    // DonateDirect
+        26 => 0,                        // Reconcile
+        27 => 8,                        // SetTarget
+        28 => 32,                       // DonateWithReceipt
+        29 => 8,                        // RequestWithdraw
+        30 => 0,                        // ConfirmWithdraw
+        31 => 0,                        // CancelWithdraw
+        32 => 32 + 1 + 4,               // DonateWithMessage
+        33 => 8,                        // ForkFunds
+        34 => 0,                        // SweepWrongMint
+        35 => 0,                        // IsAdmin
+        36 => 0,                        // WithdrawSurplus
+        37 => 8,                        // WithdrawSpl
+        _ => {
+            msg!("Unknown instruction {}!!!", tag);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+    };
+    /*
+     * only the tag byte was sent, most likely a client that forgot to serialize the request.
+     */
+    if data.is_empty() && min_len > 0 {
+        msg!("Instruction {} was sent without its payload of at least {} bytes!!!", tag, min_len);
         return Err(ProgramError::InvalidInstructionData);
     }
-    if instruction_data[0] == 0 {
-        return create_campain(
-            program_id, 
-            accounts, 
-            &instruction_data[1..instruction_data.len()],
-        );
+    if data.len() < min_len {
+        msg!("Instruction {} needs at least {} bytes of data but got {}!!!", tag, min_len, data.len());
+        return Err(ProgramError::InvalidInstructionData);
     }
-    else if instruction_data[0] == 1 {
-        return withdraw(
-            program_id,
-            accounts,
-            &instruction_data[1..instruction_data.len()],
-        );
+    Ok(())
+}
+
+/**********************************************************************/
+/*
+ * Maximum length in bytes of the strings stored in a campaign,
+ * the name is one of the seeds of the campaign address so it can't be longer than a seed.
+ */
+pub const MAX_NAME_LEN: usize = MAX_SEED_LEN;
+pub const MAX_DESCRIPTION_LEN: usize = 256;
+pub const MAX_IMAGE_LINK_LEN: usize = 128;
+
+/*
+ * Lamports a withdrawal leaves in the writing_account on top of its rent_exemption,
+ * so the campaign stays rent exempt if the rent parameters go up.
+ */
+pub const RENT_BUFFER_LAMPORTS: u64 = 10_000;
+
+/*
+ * Cut of every withdrawal sent to the platform account, in basis points (1/100 of a percent).
+ */
+pub const PLATFORM_FEE_BPS: u64 = 100;
+
+/*
+ * Withdrawals below this many lamports pay no platform fee, their fee would round down to 0 anyway.
+ */
+pub const FEE_FREE_THRESHOLD: u64 = 10_000 / PLATFORM_FEE_BPS;
+
+/*
+ * Length of the window of the daily withdrawal cap, days start at unix timestamp multiples of it.
+ */
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/*
+ * Time between request_withdraw and the earliest confirm_withdraw, the window donors get to react.
+ */
+pub const WITHDRAW_DELAY_SECONDS: i64 = 2 * SECONDS_PER_DAY;
+
+/*
+ * A new campaign stays open at least this long, no flash campaigns.
+ */
+pub const MIN_CAMPAIGN_DURATION_SECONDS: i64 = 3_600;
+
+/*
+ * Largest target_amount a campaign can have, one billion SOL is already more than all the SOL there is,
+ * anything above it is a typo.
+ */
+pub const MAX_TARGET_AMOUNT: u64 = 1_000_000_000 * solana_program::native_token::LAMPORTS_PER_SOL;
+
+/*
+ * Lamports the creator pays to the treasury for every campaign created, 0 keeps the creation free.
+ */
+pub const CREATION_FEE_LAMPORTS: u64 = 0;
+
+/*
+ * Account receiving the platform fee.
+ */
+pub mod platform {
+    solana_program::declare_id!("DEJc3GbzdksU1dNKWAwiVx9Vi8bHHRKYHdfdadRaVMKm");
+}
+
+/*
+ * Categories a campaign can be filed under, stored as a single byte in the campaign.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CampaignCategory {
+    Other = 0,
+    Medical = 1,
+    Education = 2,
+    Community = 3,
+    Environment = 4,
+    Technology = 5,
+    Creative = 6,
+}
+
+impl CampaignCategory {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(CampaignCategory::Other),
+            1 => Some(CampaignCategory::Medical),
+            2 => Some(CampaignCategory::Education),
+            3 => Some(CampaignCategory::Community),
+            4 => Some(CampaignCategory::Environment),
+            5 => Some(CampaignCategory::Technology),
+            6 => Some(CampaignCategory::Creative),
+            _ => None,
+        }
     }
-    else if instruction_data[0] == 2 {
-        return donate(
-            program_id,
-            accounts,
-            &instruction_data[1..instruction_data.len()],
-        );
+}
+
+/*
+ * Flexible campaigns can be withdrawn from at any time,
+ * all-or-nothing campaigns only once the deadline passed with the target_amount reached.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CampaignType {
+    Flexible = 0,
+    AllOrNothing = 1,
+}
+
+impl CampaignType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(CampaignType::Flexible),
+            1 => Some(CampaignType::AllOrNothing),
+            _ => None,
+        }
     }
-    msg!("No entry_point found!!!");
-    Err(ProgramError::InvalidInstructionData)
 }
 
-entrypoint!(process_instruction);
+/*
+ * Status of a campaign, stored as a single byte in the campaign.
+ * Paused by the admin (e.g. during a dispute), Closed by the admin for good,
+ * Flagged by the platform on a suspected scam so front-ends hide it. Only Active campaigns accept donations.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CampaignStatus {
+    Active = 0,
+    Paused = 1,
+    Closed = 2,
+    Flagged = 3,
+}
 
-/**********************************************************************/
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+impl CampaignStatus {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(CampaignStatus::Active),
+            1 => Some(CampaignStatus::Paused),
+            2 => Some(CampaignStatus::Closed),
+            3 => Some(CampaignStatus::Flagged),
+            _ => None,
+        }
+    }
+}
+
+/*
+ * First seed of the campaign addresses, see "campaign_pda".
+ */
+pub const CAMPAIGN_SEED: &[u8] = b"campaign";
+
+/*
+ * First seed of the admin registry addresses, see "admin_registry_pda".
+ */
+pub const ADMIN_REGISTRY_SEED: &[u8] = b"admin_registry";
+
+/*
+ * Seed of the single GlobalState address, see "global_state_pda".
+ */
+pub const GLOBAL_SEED: &[u8] = b"global";
+
+/*
+ * First seed of the donation receipt addresses, see "receipt_pda".
+ */
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+/*
+ * First seed of the donor tally addresses, see "donor_tally_pda".
+ */
+pub const DONOR_TALLY_SEED: &[u8] = b"donor_tally";
+
+/*
+ * First seed of the match pool addresses, see "matcher_pda".
+ */
+pub const MATCHER_SEED: &[u8] = b"matcher";
+
+/*
+ * Seed of the single treasury address, see "treasury_pda".
+ */
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/*
+ * Number of campaigns a single admin can create, to limit spam.
+ */
+pub const MAX_CAMPAIGNS_PER_ADMIN: u64 = 10;
 
-    struct CampainDetails {
+/*
+ * Per admin account counting the campaigns it created, stored at "admin_registry_pda".
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct AdminRegistry {
+    pub campaign_count: u64,
+}
+
+/*
+ * Program wide counters stored at "global_state_pda", so a front-end doesn't have to scan the program accounts.
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct GlobalState {
+    pub total_campaigns: u64,
+}
+
+/*
+ * Layout version written as the first byte of every campaign account.
+ */
+pub const CAMPAIGN_VERSION: u8 = 1;
+
+/// A campaign as stored in its writing_account.
+///
+/// Clients read it with the crate's own definition, the account is allocated bigger than
+/// the campaign so `deserialize` is used rather than `try_from_slice`:
+///
+/// ```
+/// use borsh::BorshDeserialize;
+/// use program::CampaignDetails;
+///
+/// fn read_campaign(account_data: &[u8]) -> std::io::Result<CampaignDetails> {
+///     CampaignDetails::deserialize(&mut &account_data[..])
+/// }
+///
+/// assert!(read_campaign(&[]).is_err());
+/// ```
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+
+    pub struct CampaignDetails {
+    /*
+     * layout version of the account, always CAMPAIGN_VERSION for new campaigns.
+     */
+        pub version: u8,
         pub admin: Pubkey,
         pub name: String,
         pub description: String,
@@ -58,214 +559,6870 @@ entrypoint!(process_instruction);
      * total amount donated to a campaign.
      */
         pub amount_donated: u64,
-    }
-
-/*----------------------------*/
-fn create_campain(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-
-    let accounts_iter = &mut accounts.iter();
-
     /*
-     * Writing account or we can call it program account.
-     * This is an account we will create in our front-end.
-     * This account should br owned by the solana program.
+     * amount of lamports the campaign is trying to raise.
      */
-    let writing_account = next_account_info(accounts_iter)?;
+        pub target_amount: u64,
     /*
-     * Account of the person creating the campaign.
+     * unix timestamp after which the campaign stops accepting donations, 0 for a campaign without deadline.
      */
-    let creator_account = next_account_info(accounts_iter)?;
+        pub deadline: i64,
     /*
-     * allow transactions just by the creator account by sign the transaction.
+     * number of successful donations made to the campaign.
      */
-    if !creator_account.is_signer {
-        msg!("The creator_account is not a signer!!!");
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    if writing_account.owner != program_id {
-        msg!("The program don't own the writing_account!!!");
-        return Err(ProgramError::IncorrectProgramId);
-    }
-
+        pub donor_count: u64,
     /*
-     * By deriving the trait "BorshDeserialize" in our "CampaignDetails" struct we have added a 
-     * method "try_from_slice" which takes in the parameter array of [u8] and creates an object of 
-     * "CampaignDetails" with it. It gives us an enum of type results. 
-     * We will use the "expect" method on result enums to and pass in the string which we can see in case of error.
-
+     * smallest donation accepted in lamports, 0 accepts any amount.
      */
-    let mut input_data = CampainDetails::try_from_slice(&instruction_data)
-    .expect("Instruction_data serialization faild!!");
-
+        pub min_donation: u64,
     /*
-     * for a campaign created the only admin should be the one who created it.
+     * one of CampaignStatus, only an Active campaign accepts donations.
      */
-    if input_data.admin != *creator_account.key {
-        msg!("Wrong Instruction data!!!");
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
+        pub status: u8,
     /*
-     * get the minimum balance we need in our program account.
+     * one of CampaignCategory, lets front-ends filter campaigns.
      */
-    let rent_exemption = Rent::get()?.minimum_balance(writing_account.data_len());
+        pub category: u8,
     /*
-     * make sure our program account (`writing_account`) has that much lamports(balance).
+     * account receiving the withdrawn lamports, can be different from the admin.
      */
-    if **writing_account.lamports.borrow() < rent_exemption {
-        msg!("The balance of writing_account is less than the rent_exemption ammount!!!");
-        return Err(ProgramError::InsufficientFunds);
-    }
+        pub beneficiary: Pubkey,
     /*
-     * initial amount donate to be zero.
+     * one of CampaignType, all-or-nothing campaigns only pay out after a successful deadline.
      */
-    input_data.amount_donated = 0;
-    input_data.serialize(&mut &mut writing_account.try_borrow_mut_data()?[..])?;
-
-    Ok(())
-}
-
-/**************************************/
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-struct WithdrawRequest {
-    pub amount: u64,
-}
-/*----------------------------*/
-fn withdraw(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-
+        pub campaign_type: u8,
     /*
-     * create iterator and get "writing_account" (program owned account) and "admin_account".
+     * most recent backer of the campaign.
      */
-    let accounts_iter = &mut accounts.iter();
-    let writing_account = next_account_info(accounts_iter)?;
-    let admin_account = next_account_info(accounts_iter)?;
-
+        pub last_donor: Pubkey,
     /*
-     * Admin account should be the signer in this trasaction.
+     * SPL token mint accepted by donate_spl, the default pubkey disables token donations.
      */
-    if !admin_account.is_signer {
-        msg!("Admin is not a signer!!!");
-        return Err(ProgramError::IncorrectProgramId);
-    }
+        pub mint: Pubkey,
     /*
-     * check if the writing account is owned by program.
+     * total amount of "mint" tokens donated to a campaign.
      */
-    if writing_account.owner != program_id {
-        msg!("Writing_account not owned bu the program!!!");
-        return Err(ProgramError:: IncorrectProgramId);
-    }
+        pub token_amount_donated: u64,
     /*
-     * we will get the data of campaign from the writing_account
-     * we stored this when we created the campaign with create_campaign function.
+     * program-owned account of a sponsor matching donations, the default pubkey disables matching.
      */
-    let campaign_data = CampainDetails::try_from_slice(*writing_account.data.borrow())
-    .expect("Deserializing data faild!!!");
-
-    if campaign_data.admin != *admin_account.key {
-        msg!("Only the account admin can withdraw");
-        return Err(ProgramError::InvalidAccountData);
-    }
+        pub matcher: Pubkey,
     /*
-     * Here we make use of the struct we created.
-     * We will get the amount of lamports admin wants to withdraw
+     * lamports the matcher still commits to match.
      */
-    let input_data = WithdrawRequest::try_from_slice(&instruction_data)
-    .expect("Instruction serialization faild!!!");
+        pub match_pool_remaining: u64,
     /*
-     * We do not want the campaign to get deleted after a withdrawal. 
-     * We want it to always have a minimum balance,
-     * So we calculate the rent_exemption and consider it.
+     * most lamports the admin can withdraw per day, 0 means no limit.
      */
-    let rent_exemption = Rent::get()?.minimum_balance(writing_account.data_len());
+        pub max_withdraw_per_day: u64,
     /*
-     * check if we have enough funds
+     * lamports withdrawn during the day of last_withdraw_ts.
      */
-    if **writing_account.lamports.borrow() - rent_exemption < input_data.amount {
-        msg!("Not enough balance to keep the account alife!!!");
-        return Err(ProgramError::InsufficientFunds);
-    }
+        pub withdrawn_today: u64,
     /*
-     * Transfer balance
-     * decrease the balance of the program account, 
-     * and increase the admin_account balance.
+     * unix timestamp of the last withdrawal.
      */
-    **writing_account.try_borrow_mut_lamports()? -= input_data.amount;
-    **admin_account.try_borrow_mut_lamports()? += input_data.amount;
-
-    Ok(())
-}
-/**************************************/
-/*
- * We want to donate to a campaign, however we can't decrease the balance of an account not owned by our program in our program.
- * This means we can't just transfer the balance as we did in the withdraw function. 
- * Solana policies state: "An account not assigned to the program cannot have its balance decrease."
- * So for this, we will create a program-owned account in our front-end and then perform the SOL token transaction.
- */
-fn donate(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    _instruction_data: &[u8],
-) -> ProgramResult {
-
-    let accounts_iter = &mut accounts.iter();
+        pub last_withdraw_ts: i64,
     /*
-     * Create 3 accounts here,
-     * First is the program-owned account containing the data of campaign we want to donate to.
-     * Second we have a donator_program_account which is also the program-owned account that only has the Lamport we would like to donate.
-     * Third we have the account of the donator.
+     * unix timestamp at which the campaign was created.
      */
-    let writing_account = next_account_info(accounts_iter)?;
-    let donator_program_account = next_account_info(accounts_iter)?;
-    let donator = next_account_info(accounts_iter)?;
-
-    if !donator.is_signer {
-        msg!("The donator is not a signer!!!");
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    if writing_account.owner != program_id {
-        msg!("writing_account not owned by the program!!!");
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    if donator_program_account.owner != program_id {
-        msg!("donator_program_account not owned by the program!!!");
-        return Err(ProgramError::IncorrectProgramId);
-    }
+        pub created_at: i64,
     /*
-     * get the campaign_data and we will increment the amount_donated.
+     * biggest single donation in lamports, for a "top backer" display.
      */
-    let mut campaign_data = CampainDetails::try_from_slice(*writing_account.data.borrow())
-    .expect("deserializing data faild!!!");
-    campaign_data.amount_donated += **donator_program_account.lamports.borrow();
+        pub largest_donation: u64,
     /*
-     * Then we do the actual transaction.
-     * Note that the donator_program_account is owned by program so it can decrease its Lamports.
+     * donor of largest_donation, the default pubkey when it was anonymous.
      */
-    **writing_account.try_borrow_mut_lamports()? += **donator_program_account.lamports.borrow();
-    **donator_program_account.try_borrow_mut_lamports()? = 0;
-
+        pub largest_donor: Pubkey,
     /*
-     * at the end of the program we will write the new updated "campaign_data" to the writing_account's data field
-     * and return the result Ok(()).
+     * share of every donation in basis points moved to the match pool instead of the campaign, needs a matcher.
      */
-    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
-
-    Ok(())
-}
-/**********************************************************************/
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        let result = 2 + 2;
-        assert_eq!(result, 4);
+        pub pool_fee_bps: u16,
+    /*
+     * unix timestamp from which the next scheduled withdrawal can be executed.
+     */
+        pub next_withdraw_ts: i64,
+    /*
+     * seconds between two scheduled withdrawals, 0 for a single one.
+     */
+        pub interval_seconds: i64,
+    /*
+     * lamports of every scheduled withdrawal, 0 when nothing is scheduled.
+     */
+        pub per_interval_amount: u64,
+    /*
+     * program-owned account holding the CampaignMetadata, the default pubkey until set_metadata.
+     */
+        pub metadata_account: Pubkey,
+    /*
+     * set on a failed all-or-nothing campaign, the funds can only go back to donors.
+     */
+        pub refunds_enabled: bool,
+    /*
+     * how many of the signers must sign a withdrawal, 0 keeps the admin alone in charge.
+     */
+        pub required_signers: u8,
+    /*
+     * the accounts allowed to approve a withdrawal, at most MAX_SIGNERS.
+     */
+        pub signers: Vec<Pubkey>,
+    /*
+     * donate only takes what is left to reach target_amount, the rest stays with the donator.
+     */
+        pub cap_at_goal: bool,
+    /*
+     * donations from the admin are refused, to show the campaign is backed by others.
+     */
+        pub block_self_donation: bool,
+    /*
+     * lamports of the withdrawal requested with request_withdraw, 0 when none is pending.
+     */
+        pub pending_withdraw_amount: u64,
+    /*
+     * unix timestamp from which confirm_withdraw can pay the pending withdrawal.
+     */
+        pub withdraw_unlock_ts: i64,
+    /*
+     * most lamports a single donor can give in total, tallied at donor_tally_pda, 0 for no limit.
+     */
+        pub max_donation_per_donor: u64,
+    /*
+     * unix timestamp of the donation that first reached target_amount, 0 until then.
+     */
+        pub goal_reached_at: i64,
+    /*
+     * rent_exemption paid for the writing_account, what the current one no longer needs is surplus.
+     */
+        pub rent_reserved: u64,
+    /*
+     * withdrawals of at least this many lamports only go through request_withdraw and confirm_withdraw, 0 for no time lock.
+     */
+        pub timelock_threshold: u64,
+    /*
+     * admin the campaign address was derived from at creation, kept for signing after TransferAdmin.
+     */
+        pub seed_admin: Pubkey,
+    /*
+     * name the campaign address was derived from at creation, kept for signing after a rename.
+     */
+        pub seed_name: String,
+    /*
+     * bump seed of the campaign address.
+     */
+        pub bump: u8,
+    }
+
+/*
+ * Former, misspelled name of CampaignDetails, kept so existing clients still build.
+ */
+pub type CampainDetails = CampaignDetails;
+
+/*----------------------------*/
+/*
+ * Size in bytes of a campaign with the longest strings allowed, clients allocate this much
+ * for a writing_account (and pay its rent) so any later update still fits.
+ * Borsh writes a String as a u32 length followed by its bytes.
+ */
+pub const fn campaign_account_size() -> usize {
+    1                               // version
+    + 32                            // admin
+    + 4 + MAX_NAME_LEN              // name
+    + 4 + MAX_DESCRIPTION_LEN       // description
+    + 4 + MAX_IMAGE_LINK_LEN        // image_link
+    + 8                             // amount_donated
+    + 8                             // target_amount
+    + 8                             // deadline
+    + 8                             // donor_count
+    + 8                             // min_donation
+    + 1                             // status
+    + 1                             // category
+    + 32                            // beneficiary
+    + 1                             // campaign_type
+    + 32                            // last_donor
+    + 32                            // mint
+    + 8                             // token_amount_donated
+    + 32                            // matcher
+    + 8                             // match_pool_remaining
+    + 8                             // max_withdraw_per_day
+    + 8                             // withdrawn_today
+    + 8                             // last_withdraw_ts
+    + 8                             // created_at
+    + 8                             // largest_donation
+    + 32                            // largest_donor
+    + 2                             // pool_fee_bps
+    + 8                             // next_withdraw_ts
+    + 8                             // interval_seconds
+    + 8                             // per_interval_amount
+    + 32                            // metadata_account
+    + 1                             // refunds_enabled
+    + 1                             // required_signers
+    + 4 + 32 * MAX_SIGNERS          // signers
+    + 1                             // cap_at_goal
+    + 1                             // block_self_donation
+    + 8                             // pending_withdraw_amount
+    + 8                             // withdraw_unlock_ts
+    + 8                             // max_donation_per_donor
+    + 8                             // goal_reached_at
+    + 8                             // rent_reserved
+    + 8                             // timelock_threshold
+    + 32                            // seed_admin
+    + 4 + MAX_NAME_LEN              // seed_name
+    + 1                             // bump
+}
+
+/*
+ * Most signers a campaign can require for its withdrawals.
+ */
+pub const MAX_SIGNERS: usize = 5;
+
+/*
+ * Limits of the CampaignMetadata, the metadata_account must be allocated big enough for what is written.
+ */
+pub const MAX_METADATA_DESCRIPTION_LEN: usize = 4096;
+pub const MAX_METADATA_LINKS: usize = 8;
+
+/*
+ * Lamports a donor gave to a campaign with max_donation_per_donor, stored at "donor_tally_pda".
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct DonorTally {
+    pub total_donated: u64,
+}
+
+/*
+ * On-chain proof of a donation written by donate_with_receipt at "receipt_pda", the donor can point to it later.
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq)]
+pub struct DonationReceipt {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    /*
+     * lamports that left the donator_program_account, the pool fee included.
+     */
+    pub amount: u64,
+    pub timestamp: i64,
+    /*
+     * message of the donor for the campaign feed, at most MAX_MESSAGE_LEN bytes, empty without one.
+     */
+    pub message: String,
+}
+
+/*
+ * Longest donor message of donate_with_message in bytes.
+ */
+pub const MAX_MESSAGE_LEN: usize = 140;
+
+/*
+ * Content of the optional metadata_account of a campaign, too long for the writing_account.
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct CampaignMetadata {
+    /*
+     * writing_account of the campaign the metadata belongs to.
+     */
+    pub campaign: Pubkey,
+    /*
+     * markdown description.
+     */
+    pub description: String,
+    /*
+     * extra links, each at most MAX_IMAGE_LINK_LEN bytes.
+     */
+    pub links: Vec<String>,
+}
+
+/*----------------------------*/
+/*
+ * Address of the writing_account of a campaign, derived from its admin and name,
+ * so a campaign can be found from those two without a registry.
+ */
+pub fn campaign_pda(program_id: &Pubkey, admin: &Pubkey, name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CAMPAIGN_SEED, admin.as_ref(), name.as_bytes()], program_id)
+}
+
+/*----------------------------*/
+/*
+ * Seeds the campaign signs with, those of its creation since its admin and name can change afterwards.
+ */
+fn campaign_signer_seeds(campaign: &CampaignDetails) -> [&[u8]; 4] {
+    [CAMPAIGN_SEED, campaign.seed_admin.as_ref(), campaign.seed_name.as_bytes(), std::slice::from_ref(&campaign.bump)]
+}
+
+/*----------------------------*/
+/*
+ * Address of the AdminRegistry of an admin, created by the program on the first campaign of the admin.
+ */
+pub fn admin_registry_pda(program_id: &Pubkey, admin: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ADMIN_REGISTRY_SEED, admin.as_ref()], program_id)
+}
+
+/*----------------------------*/
+/*
+ * Address of the GlobalState, created by the program on the first campaign created with it.
+ */
+pub fn global_state_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_SEED], program_id)
+}
+
+/*----------------------------*/
+/*
+ * Address of the DonorTally of "donor" in "campaign", created by donate on the first donation of a capped campaign.
+ */
+pub fn donor_tally_pda(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DONOR_TALLY_SEED, campaign.as_ref(), donor.as_ref()], program_id)
+}
+
+/*----------------------------*/
+/*
+ * Address of the match pool of "campaign", the only matcher a campaign can have.
+ * fund_match_pool creates it on its first top up, the sponsor pays its rent.
+ */
+pub fn matcher_pda(program_id: &Pubkey, campaign: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MATCHER_SEED, campaign.as_ref()], program_id)
+}
+
+/*----------------------------*/
+/*
+ * Address of the treasury collecting the CREATION_FEE_LAMPORTS, a plain system account holding lamports.
+ */
+pub fn treasury_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], program_id)
+}
+
+/*----------------------------*/
+/*
+ * Address of the DonationReceipt of a donation of "donor" to "campaign" made at unix timestamp "timestamp".
+ */
+pub fn receipt_pda(program_id: &Pubkey, donor: &Pubkey, campaign: &Pubkey, timestamp: i64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RECEIPT_SEED, donor.as_ref(), campaign.as_ref(), &timestamp.to_le_bytes()],
+        program_id,
+    )
+}
+
+/*----------------------------*/
+/*
+ * Read the version byte of a campaign account before deserializing it,
+ * every known layout gets its own arm, anything else is refused.
+ */
+fn check_campaign_version(data: &[u8]) -> ProgramResult {
+    match data.first() {
+        Some(&CAMPAIGN_VERSION) => Ok(()),
+        _ => {
+            msg!("Unknown campaign account version!!!");
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
+/*----------------------------*/
+/*
+ * Read the campaign stored in a writing_account, also usable by other programs composing with this one.
+ * Zeroed accounts and campaigns without an admin give "UninitializedAccount",
+ * accounts with an unknown version or data that doesn't deserialize give "InvalidAccountData",
+ * unused bytes at the end of the account are ignored.
+ */
+pub fn load_campaign(account: &AccountInfo) -> Result<CampaignDetails, ProgramError> {
+    let data = account.try_borrow_data()?;
+    if data.iter().all(|byte| *byte == 0) {
+        msg!("The campaign account is not initialized!!!");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    check_campaign_version(&data)?;
+    let campaign_data = CampaignDetails::deserialize(&mut &data[..]).map_err(|_| {
+        msg!("Deserializing campaign data faild!!!");
+        ProgramError::InvalidAccountData
+    })?;
+    if campaign_data.admin == Pubkey::default() {
+        msg!("The campaign has no admin, it is not initialized!!!");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(campaign_data)
+}
+
+/*----------------------------*/
+/*
+ * Write a campaign into its account, data bigger than the account is refused
+ * before anything is written and the unused bytes at the end are zeroed.
+ */
+fn write_campaign(account: &AccountInfo, data: &CampaignDetails) -> ProgramResult {
+    let serialized = data.try_to_vec()?;
+    if serialized.len() > account.data_len() {
+        msg!("The campaign doesn't fit in the writing_account!!!");
+        return Err(CrowdfundingError::AccountDataTooSmall.into());
+    }
+    let mut account_data = account.try_borrow_mut_data()?;
+    account_data[..serialized.len()].copy_from_slice(&serialized);
+    account_data[serialized.len()..].fill(0);
+    Ok(())
+}
+
+/*----------------------------*/
+/*
+ * Fail early with the expected accounts listed in order when a client passes too few of them,
+ * instead of the bare "not enough account keys" of next_account_info.
+ */
+fn check_accounts_len(accounts: &[AccountInfo], expected: &[&str]) -> ProgramResult {
+    if accounts.len() < expected.len() {
+        msg!(
+            "{} accounts given but {} expected, in this order: {}!!!",
+            accounts.len(),
+            expected.len(),
+            expected.join(", ")
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    Ok(())
+}
+
+/*----------------------------*/
+/*
+ * Strings longer than the limits could overflow the space allocated for the writing_account.
+ */
+fn check_text_lengths(name: &str, description: &str, image_link: &str) -> ProgramResult {
+    if name.len() > MAX_NAME_LEN {
+        msg!("name is longer than {} bytes!!!", MAX_NAME_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if description.len() > MAX_DESCRIPTION_LEN {
+        msg!("description is longer than {} bytes!!!", MAX_DESCRIPTION_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if image_link.len() > MAX_IMAGE_LINK_LEN {
+        msg!("image_link is longer than {} bytes!!!", MAX_IMAGE_LINK_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+/*----------------------------*/
+/*
+ * Schemes an image_link may start with, an empty image_link is allowed too.
+ */
+pub const IMAGE_LINK_SCHEMES: [&str; 3] = ["http://", "https://", "ipfs://"];
+
+/*
+ * Reject image links that can't be loaded by a front-end, called after check_text_lengths.
+ */
+fn check_image_link(image_link: &str) -> ProgramResult {
+    if !image_link.is_empty() && !IMAGE_LINK_SCHEMES.iter().any(|scheme| image_link.starts_with(scheme)) {
+        msg!("image_link must start with one of {:?}!!!", IMAGE_LINK_SCHEMES);
+        return Err(CrowdfundingError::InvalidImageLink.into());
+    }
+    Ok(())
+}
+
+/**************************************/
+/*
+ * Accounts of create_campain, in order.
+ */
+struct CreateAccounts<'a, 'info> {
+    /*
+     * Writing account or we can call it program account.
+     * Its address is "campaign_pda" of the admin and name of the campaign,
+     * the program creates it and makes it owned by the solana program.
+     */
+    writing_account: &'a AccountInfo<'info>,
+    /*
+     * Account of the person creating the campaign.
+     */
+    creator_account: &'a AccountInfo<'info>,
+    /*
+     * AdminRegistry of the creator, at "admin_registry_pda" of the admin.
+     */
+    registry_account: &'a AccountInfo<'info>,
+    /*
+     * The system program, it is only needed when the program creates an account.
+     */
+    system_program: Option<&'a AccountInfo<'info>>,
+    /*
+     * The GlobalState at "global_state_pda" is optional, its counter only moves when it is passed.
+     */
+    global_account: Option<&'a AccountInfo<'info>>,
+    /*
+     * The treasury at "treasury_pda" comes last, it is only needed while CREATION_FEE_LAMPORTS is not 0.
+     */
+    treasury_account: Option<&'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> CreateAccounts<'a, 'info> {
+    fn parse(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        check_accounts_len(accounts, &["writing_account", "creator_account (signer)", "registry_account"])?;
+        let accounts_iter = &mut accounts.iter();
+        let writing_account = next_account_info(accounts_iter)?;
+        let creator_account = next_account_info(accounts_iter)?;
+        let registry_account = next_account_info(accounts_iter)?;
+        let system_program = accounts_iter.next();
+        let global_account = accounts_iter.next();
+        let treasury_account = accounts_iter.next();
+        /*
+         * allow transactions just by the creator account by sign the transaction.
+         */
+        if !creator_account.is_signer {
+            msg!("The creator_account is not a signer!!!");
+            return Err(CrowdfundingError::NotSigner.into());
+        }
+        Ok(CreateAccounts {
+            writing_account,
+            creator_account,
+            registry_account,
+            system_program,
+            global_account,
+            treasury_account,
+        })
+    }
+}
+
+/*----------------------------*/
+/*
+ * Move the creation "fee" from the creator to the treasury through the system program,
+ * nothing to pay and no account needed when it is 0.
+ */
+fn pay_creation_fee<'info>(
+    program_id: &Pubkey,
+    creator_account: &AccountInfo<'info>,
+    treasury_account: Option<&AccountInfo<'info>>,
+    system_program: Option<&AccountInfo<'info>>,
+    fee: u64,
+) -> ProgramResult {
+    if fee == 0 {
+        return Ok(());
+    }
+    let treasury_account = treasury_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let system_program = system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if treasury_pda(program_id).0 != *treasury_account.key {
+        msg!("treasury_account is not the treasury address!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    invoke(
+        &system_instruction::transfer(creator_account.key, treasury_account.key, fee),
+        &[creator_account.clone(), treasury_account.clone(), system_program.clone()],
+    )?;
+    msg!("CREATION_FEE amount={} treasury={}", fee, treasury_account.key);
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Allocate the PDA "account" with "space" bytes owned by the program, "seeds" sign for it and the payer funds its rent.
+ * Anyone can send lamports to an address before it is created and create_account refuses an account holding some,
+ * so such an account only gets what it misses for the rent_exemption and is allocated and assigned instead.
+ */
+fn create_pda_account<'info>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_exemption = rent_exemption(space)?;
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, rent_exemption, space as u64, program_id),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+    let missing = rent_exemption.saturating_sub(account.lamports());
+    if missing > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, missing),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/*----------------------------*/
+/*
+ * The AdminRegistry of "admin" at "registry_account", created on its first use, the payer pays its rent.
+ */
+fn load_admin_registry<'info>(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    registry_account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: Option<&AccountInfo<'info>>,
+) -> Result<AdminRegistry, ProgramError> {
+    let (registry_address, registry_bump) = admin_registry_pda(program_id, admin);
+    if registry_address != *registry_account.key {
+        msg!("registry_account is not the admin registry address of this admin!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if registry_account.owner == program_id {
+        return Ok(AdminRegistry::try_from_slice(&registry_account.data.borrow())?);
+    }
+    let system_program = system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    create_pda_account(
+        program_id,
+        payer,
+        registry_account,
+        system_program,
+        AdminRegistry::default().try_to_vec()?.len(),
+        &[ADMIN_REGISTRY_SEED, admin.as_ref(), &[registry_bump]],
+    )?;
+    Ok(AdminRegistry::default())
+}
+
+/*----------------------------*/
+fn create_campain(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut input_data: CampaignDetails,
+) -> ProgramResult {
+
+    let CreateAccounts {
+        writing_account,
+        creator_account,
+        registry_account,
+        system_program,
+        global_account,
+        treasury_account,
+    } = CreateAccounts::parse(accounts)?;
+
+    /*
+     * for a campaign created the only admin should be the one who created it.
+     */
+    if input_data.admin != *creator_account.key {
+        msg!("Wrong Instruction data!!!");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_text_lengths(&input_data.name, &input_data.description, &input_data.image_link)?;
+    check_image_link(&input_data.image_link)?;
+    if CampaignCategory::from_u8(input_data.category).is_none() {
+        msg!("Unknown campaign category {}!!!", input_data.category);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if CampaignType::from_u8(input_data.campaign_type).is_none() {
+        msg!("Unknown campaign type {}!!!", input_data.campaign_type);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if input_data.target_amount > MAX_TARGET_AMOUNT {
+        msg!("target_amount can't be more than {} lamports!!!", MAX_TARGET_AMOUNT);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if input_data.pool_fee_bps > 10_000 || (input_data.pool_fee_bps > 0 && input_data.matcher == Pubkey::default()) {
+        msg!("pool_fee_bps must be at most 10000 and needs a matcher!!!");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let duplicate_signer = input_data
+    .signers
+    .iter()
+    .enumerate()
+    .any(|(i, signer)| input_data.signers[..i].contains(signer));
+    if input_data.signers.len() > MAX_SIGNERS
+        || input_data.required_signers as usize > input_data.signers.len()
+        || duplicate_signer
+    {
+        msg!("At most {} distinct signers and required_signers can't be more than them!!!", MAX_SIGNERS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (campaign_address, bump) = campaign_pda(program_id, &input_data.admin, &input_data.name);
+    if campaign_address != *writing_account.key {
+        msg!("writing_account is not the campaign address of this admin and name!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    /*
+     * the match pool of a campaign is its matcher_pda, owned by the program so no one else can fill or drain it.
+     */
+    if input_data.matcher != Pubkey::default() && input_data.matcher != matcher_pda(program_id, writing_account.key).0 {
+        msg!("The matcher must be the matcher address of the campaign!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    /*
+     * one more campaign for the admin, refused once MAX_CAMPAIGNS_PER_ADMIN is reached.
+     */
+    let mut registry =
+        load_admin_registry(program_id, &input_data.admin, registry_account, creator_account, system_program)?;
+    if registry.campaign_count >= MAX_CAMPAIGNS_PER_ADMIN {
+        msg!("An admin can't create more than {} campaigns!!!", MAX_CAMPAIGNS_PER_ADMIN);
+        return Err(CrowdfundingError::TooManyCampaigns.into());
+    }
+    registry.campaign_count += 1;
+
+    /*
+     * one more campaign in total when the GlobalState is passed, created on its first use.
+     */
+    let global_state = match global_account {
+        Some(global_account) => {
+            let (global_address, global_bump) = global_state_pda(program_id);
+            if global_address != *global_account.key {
+                msg!("global_account is not the global state address!!!");
+                return Err(ProgramError::InvalidSeeds);
+            }
+            let mut global_state = if global_account.owner != program_id {
+                let system_program = system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+                create_pda_account(
+                    program_id,
+                    creator_account,
+                    global_account,
+                    system_program,
+                    GlobalState::default().try_to_vec()?.len(),
+                    &[GLOBAL_SEED, &[global_bump]],
+                )?;
+                GlobalState::default()
+            } else {
+                GlobalState::try_from_slice(&global_account.data.borrow())?
+            };
+            global_state.total_campaigns = global_state
+            .total_campaigns
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+            Some((global_account, global_state))
+        }
+        None => None,
+    };
+
+    pay_creation_fee(program_id, creator_account, treasury_account, system_program, CREATION_FEE_LAMPORTS)?;
+
+    if writing_account.owner != program_id {
+        /*
+         * First use of the address, a PDA can't sign so the client can't create it,
+         * the program allocates it through the system program and the creator pays the rent.
+         * It gets the maximum campaign size so updates with longer strings still fit.
+         */
+        let system_program = system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        create_pda_account(
+            program_id,
+            creator_account,
+            writing_account,
+            system_program,
+            campaign_account_size(),
+            &[CAMPAIGN_SEED, input_data.admin.as_ref(), input_data.name.as_bytes(), &[bump]],
+        )?;
+    } else if writing_account.data.borrow().iter().any(|byte| *byte != 0) {
+        msg!("The campaign already exists!!!");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    /*
+     * a campaign that already ended can't be created, deadline 0 is a campaign without deadline.
+     * all-or-nothing campaigns pay out or refund at their deadline, so they must have one.
+     */
+    let now = current_clock(accounts)?.unix_timestamp;
+    if input_data.deadline == 0 {
+        if input_data.campaign_type == CampaignType::AllOrNothing as u8 {
+            msg!("An all-or-nothing campaign needs a deadline!!!");
+            return Err(ProgramError::InvalidArgument);
+        }
+    } else {
+        if input_data.deadline < now {
+            msg!("The campaign deadline is already in the past!!!");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if input_data.deadline < now.saturating_add(MIN_CAMPAIGN_DURATION_SECONDS) {
+            msg!("A campaign has to run for at least {} seconds!!!", MIN_CAMPAIGN_DURATION_SECONDS);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    /*
+     * the writing_account must be big enough for the serialized campaign,
+     * an under-allocated account would fail to hold it and pay rent for the wrong size.
+     */
+    let campaign_len = input_data.try_to_vec()?.len();
+    if writing_account.data_len() < campaign_len {
+        msg!(
+            "writing_account has {} bytes but the campaign needs {}!!!",
+            writing_account.data_len(),
+            campaign_len
+        );
+        return Err(CrowdfundingError::AccountDataTooSmall.into());
+    }
+
+    /*
+     * get the minimum balance we need in our program account,
+     * the data_len is at least the size of the campaign.
+     */
+    let rent_exemption = rent_exemption(writing_account.data_len())?;
+    /*
+     * make sure our program account (`writing_account`) has that much lamports(balance).
+     */
+    if **writing_account.lamports.borrow() < rent_exemption {
+        msg!("The balance of writing_account is less than the rent_exemption ammount!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+    /*
+     * stamp the layout version and creation time, initial amount donate and donor count to be zero, open for donations.
+     * Nothing the creator sends can show backers nor a withdrawal schedule before the campaign starts.
+     */
+    input_data.version = CAMPAIGN_VERSION;
+    input_data.amount_donated = 0;
+    input_data.token_amount_donated = 0;
+    input_data.match_pool_remaining = 0;
+    input_data.donor_count = 0;
+    input_data.status = CampaignStatus::Active as u8;
+    input_data.withdrawn_today = 0;
+    input_data.last_withdraw_ts = 0;
+    input_data.created_at = now;
+    input_data.last_donor = Pubkey::default();
+    input_data.largest_donation = 0;
+    input_data.largest_donor = Pubkey::default();
+    input_data.next_withdraw_ts = 0;
+    input_data.interval_seconds = 0;
+    input_data.per_interval_amount = 0;
+    input_data.metadata_account = Pubkey::default();
+    input_data.refunds_enabled = false;
+    input_data.pending_withdraw_amount = 0;
+    input_data.withdraw_unlock_ts = 0;
+    input_data.goal_reached_at = 0;
+    input_data.rent_reserved = rent_exemption;
+    input_data.seed_admin = input_data.admin;
+    input_data.seed_name = input_data.name.clone();
+    input_data.bump = bump;
+    write_campaign(writing_account, &input_data)?;
+    registry.serialize(&mut &mut registry_account.data.borrow_mut()[..])?;
+    if let Some((global_account, global_state)) = global_state {
+        global_state.serialize(&mut &mut global_account.data.borrow_mut()[..])?;
+    }
+
+    /*
+     * Event logs start with an upper case prefix (CAMPAIGN_CREATED, WITHDRAWAL, DONATION)
+     * followed by key=value pairs, so clients subscribed to the program logs can parse them.
+     */
+    msg!(
+        "CAMPAIGN_CREATED campaign={} admin={} target={}",
+        writing_account.key,
+        input_data.admin,
+        input_data.target_amount
+    );
+    /*
+     * return data, 33 bytes: the campaign address followed by its bump seed,
+     * so the client can check it derived the same campaign_pda.
+     */
+    let mut return_data = [0u8; 33];
+    return_data[..32].copy_from_slice(campaign_address.as_ref());
+    return_data[32] = bump;
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**************************************/
+/*
+ * Same as Rent::minimum_balance but with checked math, a huge data_len is an error instead of an overflow.
+ */
+fn rent_exemption(data_len: usize) -> Result<u64, ProgramError> {
+    let rent = Rent::get()?;
+    let lamports = (data_len as u64)
+    .checked_add(ACCOUNT_STORAGE_OVERHEAD)
+    .and_then(|bytes| bytes.checked_mul(rent.lamports_per_byte_year))
+    .ok_or_else(|| {
+        msg!("The rent_exemption of {} bytes overflows!!!", data_len);
+        ProgramError::InsufficientFunds
+    })?;
+    Ok((lamports as f64 * rent.exemption_threshold) as u64)
+}
+
+/*----------------------------*/
+/*
+ * Lamports of a program account that can leave it while keeping it rent exempt.
+ */
+fn available_above_rent(account: &AccountInfo) -> Result<u64, ProgramError> {
+    let rent_exemption = rent_exemption(account.data_len())?;
+    match account.lamports().checked_sub(rent_exemption) {
+        Some(available) => Ok(available),
+        None => {
+            msg!("The account is already below the rent_exemption!!!");
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        }
+    }
+}
+
+/*----------------------------*/
+/*
+ * Lamports the admin can withdraw from a campaign, everything above rent_exemption + RENT_BUFFER_LAMPORTS.
+ */
+fn available_to_withdraw(account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(available_above_rent(account)?.saturating_sub(RENT_BUFFER_LAMPORTS))
+}
+
+/*----------------------------*/
+/*
+ * Move lamports between two accounts, "from" has to be owned by the program.
+ */
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    let from_balance = from.lamports()
+    .checked_sub(amount)
+    .ok_or(ProgramError::InsufficientFunds)?;
+    let to_balance = to.lamports()
+    .checked_add(amount)
+    .ok_or(ProgramError::ArithmeticOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
+    Ok(())
+}
+
+/*----------------------------*/
+/*
+ * Platform fee taken on a withdrawal of "amount" lamports, none below FEE_FREE_THRESHOLD,
+ * computed on u128 so the multiplication can't overflow, the result is never above "amount".
+ */
+fn platform_fee(amount: u64) -> u64 {
+    if amount < FEE_FREE_THRESHOLD {
+        return 0;
+    }
+    (amount as u128 * PLATFORM_FEE_BPS as u128 / 10_000) as u64
+}
+
+/*----------------------------*/
+/*
+ * Pay out "amount" lamports of the writing_account, the platform_account gets the platform_fee
+ * and the recipient_account the rest. Every withdraw path pays through it, returns the fee.
+ */
+fn pay_out(
+    writing_account: &AccountInfo,
+    platform_account: &AccountInfo,
+    recipient_account: &AccountInfo,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let fee = platform_fee(amount);
+    if fee > 0 {
+        move_lamports(writing_account, platform_account, fee)?;
+    }
+    move_lamports(writing_account, recipient_account, amount - fee)?;
+    Ok(fee)
+}
+
+/*----------------------------*/
+/*
+ * Lamports the matcher adds to a donation of "donated" lamports,
+ * as much as the donation while the pool lasts, clamped to what is left in the pool.
+ */
+fn compute_match(donated: u64, pool: u64) -> u64 {
+    donated.min(pool)
+}
+
+/*----------------------------*/
+/*
+ * Progress of a campaign towards its goal in basis points (10000 = goal reached),
+ * never above 10000 even when over-funded and 0 when the campaign has no target_amount.
+ */
+pub fn progress_bps(campaign: &CampaignDetails) -> u64 {
+    if campaign.target_amount == 0 {
+        return 0;
+    }
+    (campaign.amount_donated as u128 * 10_000 / campaign.target_amount as u128).min(10_000) as u64
+}
+
+/*----------------------------*/
+/*
+ * The one place deciding whether a campaign's status lets it accept donations.
+ */
+fn ensure_active(campaign: &CampaignDetails) -> ProgramResult {
+    match CampaignStatus::from_u8(campaign.status) {
+        Some(CampaignStatus::Active) => Ok(()),
+        Some(CampaignStatus::Paused) => {
+            msg!("Campaign is paused");
+            Err(CrowdfundingError::CampaignPaused.into())
+        }
+        Some(CampaignStatus::Closed) => {
+            msg!("Campaign is closed");
+            Err(CrowdfundingError::CampaignClosed.into())
+        }
+        Some(CampaignStatus::Flagged) => {
+            msg!("Campaign is flagged");
+            Err(CrowdfundingError::CampaignFlagged.into())
+        }
+        None => {
+            msg!("Unknown campaign status {}!!!", campaign.status);
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
+/*----------------------------*/
+/*
+ * The Clock read from the Clock sysvar account when the instruction passes one, in any position,
+ * for runtimes without the sysvar syscalls. Without it "Clock::get()" is used.
+ */
+fn current_clock(accounts: &[AccountInfo]) -> Result<Clock, ProgramError> {
+    match accounts.iter().find(|account| sysvar::clock::check_id(account.key)) {
+        Some(clock_account) => Clock::from_account_info(clock_account),
+        None => Clock::get(),
+    }
+}
+
+/*----------------------------*/
+/*
+ * True once "now" is past the deadline of the campaign, a campaign with deadline 0 has none and never expires.
+ * Donations are refused from then on, clients use it to show the same state.
+ */
+pub fn is_expired(campaign: &CampaignDetails, now: i64) -> bool {
+    campaign.deadline != 0 && now > campaign.deadline
+}
+
+/*----------------------------*/
+/*
+ * True for an all-or-nothing campaign whose deadline passed without reaching its goal.
+ * Its lamports belong to the donors, they can only go back to them with refund.
+ */
+pub fn is_failed(campaign: &CampaignDetails, now: i64) -> bool {
+    campaign.campaign_type == CampaignType::AllOrNothing as u8
+        && is_expired(campaign, now)
+        && campaign.amount_donated < campaign.target_amount
+}
+
+/*----------------------------*/
+/*
+ * True once a campaign got more than its target_amount, a campaign without target_amount never is.
+ */
+pub fn is_overfunded(campaign: &CampaignDetails) -> bool {
+    campaign.target_amount > 0 && campaign.amount_donated > campaign.target_amount
+}
+
+/*----------------------------*/
+/*
+ * "lamports" as a SOL amount for display, 9 decimals with the trailing zeros trimmed ("1.5", "0.000000001", "2").
+ */
+#[cfg(feature = "client")]
+pub fn lamports_to_sol_string(lamports: u64) -> String {
+    let sol = lamports / solana_program::native_token::LAMPORTS_PER_SOL;
+    let fraction = lamports % solana_program::native_token::LAMPORTS_PER_SOL;
+    if fraction == 0 {
+        return sol.to_string();
+    }
+    format!("{}.{}", sol, format!("{:09}", fraction).trim_end_matches('0'))
+}
+
+/*----------------------------*/
+/*
+ * Lamports still missing to reach the goal ("only X lamports left!"), 0 once it is reached.
+ */
+pub fn remaining_to_goal(campaign: &CampaignDetails) -> u64 {
+    campaign.target_amount.saturating_sub(campaign.amount_donated)
+}
+
+/*
+ * Everything a client needs to render a campaign card, the stored fields it shows with the computed ones next to them.
+ * The withdrawal bookkeeping (daily cap, schedule, pending withdrawal, signers list) is left out.
+ */
+#[cfg(feature = "client")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CampaignView {
+    pub admin: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub image_link: String,
+    pub amount_donated: u64,
+    pub target_amount: u64,
+    pub deadline: i64,
+    pub donor_count: u64,
+    pub min_donation: u64,
+    pub status: u8,
+    pub category: u8,
+    pub beneficiary: Pubkey,
+    pub campaign_type: u8,
+    pub created_at: i64,
+    pub largest_donation: u64,
+    pub largest_donor: Pubkey,
+    pub last_donor: Pubkey,
+    pub goal_reached_at: i64,
+    pub mint: Pubkey,
+    pub token_amount_donated: u64,
+    pub matcher: Pubkey,
+    pub match_pool_remaining: u64,
+    pub cap_at_goal: bool,
+    pub refunds_enabled: bool,
+    pub required_signers: u8,
+    pub progress_bps: u64,
+    pub remaining_to_goal: u64,
+    pub is_expired: bool,
+}
+
+/*----------------------------*/
+/*
+ * The CampaignView of "campaign" at "now", one call for the whole campaign card.
+ */
+#[cfg(feature = "client")]
+pub fn view(campaign: &CampaignDetails, now: i64) -> CampaignView {
+    CampaignView {
+        admin: campaign.admin,
+        name: campaign.name.clone(),
+        description: campaign.description.clone(),
+        image_link: campaign.image_link.clone(),
+        amount_donated: campaign.amount_donated,
+        target_amount: campaign.target_amount,
+        deadline: campaign.deadline,
+        donor_count: campaign.donor_count,
+        min_donation: campaign.min_donation,
+        status: campaign.status,
+        category: campaign.category,
+        beneficiary: campaign.beneficiary,
+        campaign_type: campaign.campaign_type,
+        created_at: campaign.created_at,
+        largest_donation: campaign.largest_donation,
+        largest_donor: campaign.largest_donor,
+        last_donor: campaign.last_donor,
+        goal_reached_at: campaign.goal_reached_at,
+        mint: campaign.mint,
+        token_amount_donated: campaign.token_amount_donated,
+        matcher: campaign.matcher,
+        match_pool_remaining: campaign.match_pool_remaining,
+        cap_at_goal: campaign.cap_at_goal,
+        refunds_enabled: campaign.refunds_enabled,
+        required_signers: campaign.required_signers,
+        progress_bps: progress_bps(campaign),
+        remaining_to_goal: remaining_to_goal(campaign),
+        is_expired: is_expired(campaign, now),
+    }
+}
+
+/*----------------------------*/
+/*
+ * The state checks of every payout to the beneficiary or the admin (withdrawals, forks, surplus).
+ * A flagged campaign's funds are frozen pending investigation, a paused one can still pay out.
+ * All-or-nothing campaigns keep the funds until the deadline and only pay out a reached goal,
+ * a failed one (or one with refunds enabled) only pays back its donors.
+ */
+fn check_payout(campaign: &CampaignDetails, now: i64) -> ProgramResult {
+    if campaign.status == CampaignStatus::Flagged as u8 {
+        msg!("Campaign is flagged, withdrawals are frozen!!!");
+        return Err(CrowdfundingError::CampaignFlagged.into());
+    }
+    if campaign.campaign_type == CampaignType::AllOrNothing as u8 && !is_expired(campaign, now) {
+        msg!("All-or-nothing campaign is still running!!!");
+        return Err(CrowdfundingError::CampaignStillActive.into());
+    }
+    if is_failed(campaign, now) || campaign.refunds_enabled {
+        msg!("All-or-nothing campaign did not reach its goal, its funds go back to the donors!!!");
+        return Err(CrowdfundingError::GoalNotReached.into());
+    }
+    Ok(())
+}
+
+/*----------------------------*/
+/*
+ * Count a withdrawal of "amount" made at "now" against the daily cap of the campaign,
+ * the counter starts again from zero on the first withdrawal of a new day.
+ */
+fn record_daily_withdrawal(campaign: &mut CampaignDetails, amount: u64, now: i64) -> ProgramResult {
+    if now.div_euclid(SECONDS_PER_DAY) != campaign.last_withdraw_ts.div_euclid(SECONDS_PER_DAY) {
+        campaign.withdrawn_today = 0;
+    }
+    let withdrawn_today = campaign.withdrawn_today
+    .checked_add(amount)
+    .ok_or(ProgramError::ArithmeticOverflow)?;
+    if campaign.max_withdraw_per_day != 0 && withdrawn_today > campaign.max_withdraw_per_day {
+        msg!(
+            "Only {} lamports can be withdrawn per day, {} already withdrawn today!!!",
+            campaign.max_withdraw_per_day,
+            campaign.withdrawn_today
+        );
+        return Err(CrowdfundingError::DailyWithdrawLimitExceeded.into());
+    }
+    campaign.withdrawn_today = withdrawn_today;
+    campaign.last_withdraw_ts = now;
+    Ok(())
+}
+
+/*----------------------------*/
+/*
+ * Payouts of at least timelock_threshold lamports only go through request_withdraw and confirm_withdraw,
+ * so the donors see them coming WITHDRAW_DELAY_SECONDS ahead.
+ */
+fn check_timelock(campaign: &CampaignDetails, amount: u64) -> ProgramResult {
+    if campaign.timelock_threshold != 0 && amount >= campaign.timelock_threshold {
+        msg!(
+            "Withdrawals of {} lamports or more have to be requested with request_withdraw!!!",
+            campaign.timelock_threshold
+        );
+        return Err(CrowdfundingError::WithdrawalTimeLocked.into());
+    }
+    Ok(())
+}
+
+/**************************************/
+/*
+ * Accounts every admin instruction starts with, in order: "writing_account" (program owned account)
+ * and "admin_account" (signer). The accounts a handler needs after them are in "other_accounts".
+ */
+#[derive(Clone, Copy)]
+struct AdminAccounts<'a, 'info> {
+    writing_account: &'a AccountInfo<'info>,
+    admin_account: &'a AccountInfo<'info>,
+    other_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> AdminAccounts<'a, 'info> {
+    fn parse(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        check_accounts_len(accounts, &["writing_account", "admin_account (signer)"])?;
+        let accounts_iter = &mut accounts.iter();
+        let writing_account = next_account_info(accounts_iter)?;
+        let admin_account = next_account_info(accounts_iter)?;
+
+        if !admin_account.is_signer {
+            msg!("Admin is not a signer!!!");
+            return Err(CrowdfundingError::NotSigner.into());
+        }
+        if writing_account.owner != program_id {
+            msg!("writing_account not owned by the program!!!");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(AdminAccounts { writing_account, admin_account, other_accounts: &accounts[2..] })
+    }
+
+    /*
+     * The campaign of the writing_account, refused unless the admin_account is its admin.
+     * "action" completes the log: "Only the account admin can <action>".
+     */
+    fn load_campaign(&self, action: &str) -> Result<CampaignDetails, ProgramError> {
+        let campaign_data = load_campaign(self.writing_account)?;
+        if campaign_data.admin != *self.admin_account.key {
+            msg!("Only the account admin can {}", action);
+            return Err(CrowdfundingError::WrongAdmin.into());
+        }
+        Ok(campaign_data)
+    }
+}
+
+/*----------------------------*/
+/*
+ * M-of-N campaigns: count the configured signers that signed, the admin counts if it is one of them.
+ * Every instruction moving lamports out of a campaign checks it, the other signers come after its own accounts.
+ */
+fn check_approvals<'info>(
+    campaign: &CampaignDetails,
+    admin_account: &AccountInfo<'info>,
+    co_signer_accounts: &[AccountInfo<'info>],
+) -> ProgramResult {
+    let approvals = campaign
+    .signers
+    .iter()
+    .filter(|signer| {
+        std::iter::once(admin_account)
+        .chain(co_signer_accounts)
+        .any(|account| account.is_signer && account.key == *signer)
+    })
+    .count();
+    if approvals < campaign.required_signers as usize {
+        msg!("{} of the {} required signers signed!!!", approvals, campaign.required_signers);
+        return Err(CrowdfundingError::NotEnoughSigners.into());
+    }
+    Ok(())
+}
+
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct WithdrawRequest {
+    pub amount: u64,
+}
+/**************************************/
+/*
+ * Accounts of withdraw and can_withdraw, in order: "writing_account" (program owned account), "admin_account",
+ * the "platform_account" receiving the fee and the "beneficiary_account" receiving the rest.
+ * The other signers of a campaign with required_signers come after them.
+ */
+#[derive(Clone, Copy)]
+struct WithdrawAccounts<'a, 'info> {
+    writing_account: &'a AccountInfo<'info>,
+    admin_account: &'a AccountInfo<'info>,
+    platform_account: &'a AccountInfo<'info>,
+    beneficiary_account: &'a AccountInfo<'info>,
+    co_signer_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> WithdrawAccounts<'a, 'info> {
+    fn parse(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        check_accounts_len(
+            accounts,
+            &["writing_account", "admin_account (signer)", "platform_account", "beneficiary_account"],
+        )?;
+        let accounts_iter = &mut accounts.iter();
+        let writing_account = next_account_info(accounts_iter)?;
+        let admin_account = next_account_info(accounts_iter)?;
+        let platform_account = next_account_info(accounts_iter)?;
+        let beneficiary_account = next_account_info(accounts_iter)?;
+        /*
+         * Admin account should be the signer in this trasaction.
+         */
+        if !admin_account.is_signer {
+            msg!("Admin is not a signer!!!");
+            return Err(CrowdfundingError::NotSigner.into());
+        }
+        /*
+         * check if the writing account is owned by program.
+         */
+        if writing_account.owner != program_id {
+            msg!("Writing_account not owned bu the program!!!");
+            return Err(ProgramError:: IncorrectProgramId);
+        }
+        Ok(WithdrawAccounts {
+            writing_account,
+            admin_account,
+            platform_account,
+            beneficiary_account,
+            co_signer_accounts: &accounts[4..],
+        })
+    }
+}
+
+/*----------------------------*/
+/*
+ * Every check of a withdraw of "amount" lamports, without moving anything.
+ * Returns the campaign with the withdrawal counted against the daily cap, ready to be written.
+ * Shared by withdraw and can_withdraw so the two can't drift apart.
+ */
+fn check_withdraw(accounts: &WithdrawAccounts, amount: u64) -> Result<CampaignDetails, ProgramError> {
+    let WithdrawAccounts {
+        writing_account,
+        admin_account,
+        platform_account,
+        beneficiary_account,
+        co_signer_accounts,
+    } = *accounts;
+    /*
+     * we will get the data of campaign from the writing_account
+     * we stored this when we created the campaign with create_campaign function.
+     */
+    let mut campaign_data = load_campaign(writing_account)?;
+
+    if campaign_data.admin != *admin_account.key {
+        msg!("Only the account admin can withdraw");
+        return Err(CrowdfundingError::WrongAdmin.into());
+    }
+    let now = current_clock(co_signer_accounts)?.unix_timestamp;
+    check_payout(&campaign_data, now)?;
+    check_approvals(&campaign_data, admin_account, co_signer_accounts)?;
+    if !platform::check_id(platform_account.key) {
+        msg!("Wrong platform_account!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if campaign_data.beneficiary != *beneficiary_account.key {
+        msg!("beneficiary_account is not the beneficiary of the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    /*
+     * We do not want the campaign to get deleted after a withdrawal. 
+     * We want it to always have a minimum balance,
+     * So only the lamports above the rent_exemption and RENT_BUFFER_LAMPORTS can be withdrawn.
+     */
+    if available_to_withdraw(writing_account)? < amount {
+        msg!("Not enough balance to keep the account alife!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+    record_daily_withdrawal(&mut campaign_data, amount, now)?;
+    Ok(campaign_data)
+}
+/*----------------------------*/
+fn withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: WithdrawRequest,
+) -> ProgramResult {
+
+    let withdraw_accounts = WithdrawAccounts::parse(program_id, accounts)?;
+    let campaign_data = check_withdraw(&withdraw_accounts, input_data.amount)?;
+    check_timelock(&campaign_data, input_data.amount)?;
+    pay_withdrawal(&withdraw_accounts, &campaign_data, input_data.amount)
+}
+/*----------------------------*/
+/*
+ * Write the campaign returned by check_withdraw and pay out "amount" lamports.
+ */
+fn pay_withdrawal(accounts: &WithdrawAccounts, campaign_data: &CampaignDetails, amount: u64) -> ProgramResult {
+    let WithdrawAccounts { writing_account, platform_account, beneficiary_account, .. } = *accounts;
+    write_campaign(writing_account, campaign_data)?;
+    /*
+     * Transfer balance
+     * decrease the balance of the program account by the full amount,
+     * the platform_account gets the fee and the beneficiary_account the rest.
+     */
+    let fee = pay_out(writing_account, platform_account, beneficiary_account, amount)?;
+
+    msg!(
+        "WITHDRAWAL amount={} fee={} beneficiary={} campaign={}",
+        amount,
+        fee,
+        beneficiary_account.key,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * First phase of a time-locked withdrawal: record "amount" as pending,
+ * confirm_withdraw pays it once WITHDRAW_DELAY_SECONDS have passed. A new request replaces the pending one.
+ */
+fn request_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: WithdrawRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("request a withdrawal")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    if input_data.amount == 0 {
+        msg!("Nothing to withdraw!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    campaign_data.pending_withdraw_amount = input_data.amount;
+    campaign_data.withdraw_unlock_ts = current_clock(accounts)?
+    .unix_timestamp
+    .checked_add(WITHDRAW_DELAY_SECONDS)
+    .ok_or(ProgramError::ArithmeticOverflow)?;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!(
+        "WITHDRAWAL_REQUESTED amount={} unlock_ts={} campaign={}",
+        campaign_data.pending_withdraw_amount,
+        campaign_data.withdraw_unlock_ts,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Second phase: pay the pending withdrawal with the same accounts and checks as withdraw,
+ * only once its unlock time has come. The time lock of withdraw doesn't apply, the request waited for it.
+ */
+fn confirm_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let writing_account = next_account_info(&mut accounts.iter())?;
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let campaign_data = load_campaign(writing_account)?;
+    if campaign_data.pending_withdraw_amount == 0 {
+        msg!("No withdrawal is pending!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if current_clock(accounts)?.unix_timestamp < campaign_data.withdraw_unlock_ts {
+        msg!("The pending withdrawal unlocks at {}!!!", campaign_data.withdraw_unlock_ts);
+        return Err(CrowdfundingError::ScheduledWithdrawalNotDue.into());
+    }
+
+    let amount = campaign_data.pending_withdraw_amount;
+    let withdraw_accounts = WithdrawAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = check_withdraw(&withdraw_accounts, amount)?;
+    campaign_data.pending_withdraw_amount = 0;
+    campaign_data.withdraw_unlock_ts = 0;
+    pay_withdrawal(&withdraw_accounts, &campaign_data, amount)
+}
+/*----------------------------*/
+/*
+ * Drop the pending withdrawal of request_withdraw, for an admin who requested it by mistake.
+ */
+fn cancel_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("cancel a withdrawal")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    if campaign_data.pending_withdraw_amount == 0 {
+        msg!("No withdrawal is pending!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    campaign_data.pending_withdraw_amount = 0;
+    campaign_data.withdraw_unlock_ts = 0;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("WITHDRAWAL_CANCELLED campaign={}", writing_account.key);
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Withdraw everything above rent_exemption + RENT_BUFFER_LAMPORTS, same accounts and checks as withdraw
+ * (the daily cap still applies), so the client doesn't have to compute the amount. Fails when there is nothing to withdraw.
+ * return data, 8 bytes: the withdrawn amount (u64 little endian).
+ */
+fn withdraw_all(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let writing_account = next_account_info(&mut accounts.iter())?;
+    if writing_account.owner != program_id {
+        msg!("Writing_account not owned bu the program!!!");
+        return Err(ProgramError:: IncorrectProgramId);
+    }
+    let amount = available_to_withdraw(writing_account)?;
+    if amount == 0 {
+        msg!("Nothing above the rent_exemption and RENT_BUFFER_LAMPORTS to withdraw!!!");
+        return Err(ProgramError::InsufficientFunds);
+    }
+    withdraw(program_id, accounts, WithdrawRequest { amount })?;
+    set_return_data(&amount.to_le_bytes());
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Dry run of withdraw with the same accounts: succeeds or fails with the error withdraw would give,
+ * nothing is written and no lamport moves, so clients can simulate it before withdrawing.
+ */
+fn can_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: WithdrawRequest,
+) -> ProgramResult {
+
+    let campaign_data = check_withdraw(&WithdrawAccounts::parse(program_id, accounts)?, input_data.amount)?;
+    check_timelock(&campaign_data, input_data.amount)?;
+    msg!("Withdrawal of {} lamports is possible", input_data.amount);
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Whether the candidate account is the admin of the campaign, so front-ends know when to show the admin controls.
+ * accounts: [writing_account, candidate], nothing is written.
+ * return data, 1 byte: 1 for the admin, 0 otherwise.
+ */
+fn is_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let candidate_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let campaign_data = load_campaign(writing_account)?;
+
+    let is_admin = campaign_data.admin == *candidate_account.key;
+    msg!("IS_ADMIN candidate={} is_admin={} campaign={}", candidate_account.key, is_admin, writing_account.key);
+    set_return_data(&[is_admin as u8]);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ScheduleWithdrawalRequest {
+    pub next_withdraw_ts: i64,
+    pub interval_seconds: i64,
+    /*
+     * 0 cancels the schedule.
+     */
+    pub per_interval_amount: u64,
+}
+/*----------------------------*/
+/*
+ * Milestone style disbursements: the admin schedules "per_interval_amount" lamports to the beneficiary
+ * from "next_withdraw_ts" and then every "interval_seconds", anyone can execute them once due.
+ * The other signers of a campaign with required_signers come after the admin, they approve the whole schedule.
+ */
+fn schedule_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: ScheduleWithdrawalRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("schedule withdrawals")?;
+    let AdminAccounts { writing_account, admin_account, other_accounts } = admin_accounts;
+    check_approvals(&campaign_data, admin_account, other_accounts)?;
+    if input_data.interval_seconds < 0 {
+        msg!("interval_seconds can't be negative!!!");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    campaign_data.next_withdraw_ts = input_data.next_withdraw_ts;
+    campaign_data.interval_seconds = input_data.interval_seconds;
+    campaign_data.per_interval_amount = input_data.per_interval_amount;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!(
+        "WITHDRAWAL_SCHEDULED amount={} next={} interval={} campaign={}",
+        input_data.per_interval_amount,
+        input_data.next_withdraw_ts,
+        input_data.interval_seconds,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Permissionless, send the scheduled amount to the beneficiary once next_withdraw_ts is reached
+ * and move next_withdraw_ts one interval further (a schedule without interval runs once).
+ * Accounts: writing_account, platform_account, beneficiary_account, the platform fee and daily cap apply.
+ */
+fn execute_scheduled_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let platform_account = next_account_info(accounts_iter)?;
+    let beneficiary_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut campaign_data = load_campaign(writing_account)?;
+
+    let now = current_clock(accounts)?.unix_timestamp;
+    check_payout(&campaign_data, now)?;
+    if !platform::check_id(platform_account.key) {
+        msg!("Wrong platform_account!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if campaign_data.beneficiary != *beneficiary_account.key {
+        msg!("beneficiary_account is not the beneficiary of the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amount = campaign_data.per_interval_amount;
+    if amount == 0 {
+        msg!("No withdrawal is scheduled!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    check_timelock(&campaign_data, amount)?;
+    if now < campaign_data.next_withdraw_ts {
+        msg!("The scheduled withdrawal is due at {}!!!", campaign_data.next_withdraw_ts);
+        return Err(CrowdfundingError::ScheduledWithdrawalNotDue.into());
+    }
+    if available_to_withdraw(writing_account)? < amount {
+        msg!("Not enough balance to keep the account alife!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+    record_daily_withdrawal(&mut campaign_data, amount, now)?;
+    if campaign_data.interval_seconds == 0 {
+        campaign_data.per_interval_amount = 0;
+    } else {
+        campaign_data.next_withdraw_ts = campaign_data.next_withdraw_ts
+        .checked_add(campaign_data.interval_seconds)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+    write_campaign(writing_account, &campaign_data)?;
+
+    let fee = pay_out(writing_account, platform_account, beneficiary_account, amount)?;
+
+    msg!(
+        "WITHDRAWAL amount={} fee={} beneficiary={} campaign={}",
+        amount,
+        fee,
+        beneficiary_account.key,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetMetadataRequest {
+    pub description: String,
+    pub links: Vec<String>,
+}
+/*----------------------------*/
+/*
+ * Write the long description and links of a campaign into its metadata_account, admin only.
+ * The client allocates the metadata_account owned by the program, the first set_metadata
+ * links it to the campaign and later calls must use that same account.
+ */
+fn set_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: SetMetadataRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let AdminAccounts { writing_account, other_accounts, .. } = admin_accounts;
+    let metadata_account = next_account_info(&mut other_accounts.iter())?;
+
+    if metadata_account.owner != program_id {
+        msg!("metadata_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut campaign_data = admin_accounts.load_campaign("set the metadata")?;
+    if input_data.description.len() > MAX_METADATA_DESCRIPTION_LEN
+        || input_data.links.len() > MAX_METADATA_LINKS
+        || input_data.links.iter().any(|link| link.len() > MAX_IMAGE_LINK_LEN)
+    {
+        msg!(
+            "The metadata is limited to a {} bytes description and {} links of {} bytes!!!",
+            MAX_METADATA_DESCRIPTION_LEN,
+            MAX_METADATA_LINKS,
+            MAX_IMAGE_LINK_LEN
+        );
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    /*
+     * a fresh (zeroed) metadata_account can be linked once, after that only the linked one is accepted.
+     */
+    if campaign_data.metadata_account == Pubkey::default() {
+        if metadata_account.data.borrow().iter().any(|byte| *byte != 0) {
+            msg!("metadata_account is already in use!!!");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        campaign_data.metadata_account = *metadata_account.key;
+        write_campaign(writing_account, &campaign_data)?;
+    } else if campaign_data.metadata_account != *metadata_account.key {
+        msg!("metadata_account is not the metadata account of the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let metadata = CampaignMetadata {
+        campaign: *writing_account.key,
+        description: input_data.description,
+        links: input_data.links,
+    };
+    let serialized = metadata.try_to_vec()?;
+    if serialized.len() > metadata_account.data_len() {
+        msg!("The metadata doesn't fit in the metadata_account!!!");
+        return Err(CrowdfundingError::AccountDataTooSmall.into());
+    }
+    let mut metadata_data = metadata_account.try_borrow_mut_data()?;
+    metadata_data[..serialized.len()].copy_from_slice(&serialized);
+    metadata_data[serialized.len()..].fill(0);
+
+    msg!("METADATA_SET metadata={} campaign={}", metadata_account.key, writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+/*
+ * An all-or-nothing campaign that missed its goal at the deadline pays nothing out,
+ * the admin enables refunds and sends the donations back with refund,
+ * the program makes sure none of it reaches the admin or the beneficiary.
+ */
+fn enable_refunds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("enable refunds")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    if campaign_data.campaign_type != CampaignType::AllOrNothing as u8 {
+        msg!("Only all-or-nothing campaigns have refunds!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !is_expired(&campaign_data, current_clock(accounts)?.unix_timestamp) {
+        msg!("All-or-nothing campaign is still running!!!");
+        return Err(CrowdfundingError::CampaignStillActive.into());
+    }
+    if campaign_data.amount_donated >= campaign_data.target_amount {
+        msg!("All-or-nothing campaign reached its goal!!!");
+        return Err(CrowdfundingError::GoalReached.into());
+    }
+
+    campaign_data.refunds_enabled = true;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("REFUNDS_ENABLED campaign={}", writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ExtendDeadlineRequest {
+    pub new_deadline: i64,
+}
+/*----------------------------*/
+/*
+ * Give a campaign more time, the deadline can only move later and not into the past.
+ */
+fn extend_deadline(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: ExtendDeadlineRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("extend the deadline")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    /*
+     * deadline 0 is no deadline, setting one would shorten the campaign instead of extending it.
+     */
+    if campaign_data.deadline == 0 {
+        msg!("The campaign has no deadline to extend!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    /*
+     * a failed all-or-nothing campaign owes its donors their lamports, more time would let it pay out anyway.
+     */
+    let now = current_clock(accounts)?.unix_timestamp;
+    if is_failed(&campaign_data, now) || campaign_data.refunds_enabled {
+        msg!("All-or-nothing campaign did not reach its goal, its deadline can't move!!!");
+        return Err(CrowdfundingError::GoalNotReached.into());
+    }
+    if input_data.new_deadline <= campaign_data.deadline {
+        msg!("The new deadline must be after the current one ({})!!!", campaign_data.deadline);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if input_data.new_deadline < now {
+        msg!("The new deadline is already in the past!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    campaign_data.deadline = input_data.new_deadline;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("DEADLINE_EXTENDED deadline={} campaign={}", campaign_data.deadline, writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DonateDirectRequest {
+    pub amount: u64,
+    /*
+     * same as the anonymous flag of DonateRequest.
+     */
+    pub anonymous: bool,
+}
+/*----------------------------*/
+/*
+ * Donate "amount" lamports straight from the donator wallet with a system program transfer (CPI),
+ * no donator_program_account needed. Accounts: writing_account, donator (signer), system_program,
+ * then the donor_tally_account and the system program when the campaign has a max_donation_per_donor.
+ * Direct donations are not matched, with cap_at_goal only what is left to reach the goal is transferred.
+ */
+fn donate_direct(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: DonateDirectRequest,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !donator.is_signer {
+        msg!("The donator is not a signer!!!");
+        return Err(CrowdfundingError::NotSigner.into());
+    }
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !system_program::check_id(system_program.key) {
+        msg!("Wrong system program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if input_data.amount == 0 {
+        msg!("Nothing to donate");
+        return Err(CrowdfundingError::NothingToDonate.into());
+    }
+    let mut campaign_data = load_campaign(writing_account)?;
+    let now = current_clock(accounts)?.unix_timestamp;
+    let donated = accept_donation(&campaign_data, writing_account, donator.key, input_data.amount, now)?;
+    let mut donor_tally =
+        DonorTallyAccounts::parse(program_id, &campaign_data, writing_account.key, donator.key, accounts_iter)?;
+    if let Some(donor_tally) = donor_tally.as_mut() {
+        donor_tally.add(&campaign_data, donated)?;
+    }
+    let donor = if input_data.anonymous { None } else { Some(donator.key) };
+    record_donation(&mut campaign_data, writing_account.key, donor, donated, donated, now)?;
+    write_campaign(writing_account, &campaign_data)?;
+    if let Some(donor_tally) = donor_tally {
+        donor_tally.write(program_id, writing_account.key, donator)?;
+    }
+
+    invoke(
+        &system_instruction::transfer(donator.key, writing_account.key, donated),
+        &[donator.clone(), writing_account.clone(), system_program.clone()],
+    )?;
+
+    msg!(
+        "DONATION amount={} matched=0 pool_fee=0 total={} campaign={}",
+        donated,
+        campaign_data.amount_donated,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * amount_donated only counts what went through the donate instructions,
+ * lamports sent straight to the writing_account are invisible to it.
+ * The admin raises it to the balance above the rent_exemption to catch up with the account,
+ * it never goes down since withdrawals leave the raised total as it is.
+ * All-or-nothing campaigns can't reconcile, the admin's own transfers would push them past their goal.
+ */
+fn reconcile(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("reconcile the campaign")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    if campaign_data.campaign_type == CampaignType::AllOrNothing as u8 {
+        msg!("An all-or-nothing campaign only counts the donations made through donate!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let previous = campaign_data.amount_donated;
+    campaign_data.amount_donated = previous.max(available_above_rent(writing_account)?);
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!(
+        "RECONCILED previous={} amount_donated={} campaign={}",
+        previous,
+        campaign_data.amount_donated,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * The rent_exemption is computed again on every call, when it drops below the rent_reserved at creation
+ * the difference is no longer needed as rent. It goes to the beneficiary like a withdrawal, same accounts and checks.
+ * Lamports sent straight to the writing_account are untracked donations (see reconcile), not surplus.
+ * return data, 8 bytes: the withdrawn surplus (u64 little endian).
+ */
+fn withdraw_surplus(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let withdraw_accounts = WithdrawAccounts::parse(program_id, accounts)?;
+    let WithdrawAccounts { writing_account, .. } = withdraw_accounts;
+    let rent_now = rent_exemption(writing_account.data_len())?;
+    let surplus = load_campaign(writing_account)?.rent_reserved.saturating_sub(rent_now);
+    if surplus == 0 {
+        msg!("The rent_exemption is not below the rent_reserved, no surplus!!!");
+        return Err(ProgramError::InsufficientFunds);
+    }
+    let mut campaign_data = check_withdraw(&withdraw_accounts, surplus)?;
+    campaign_data.rent_reserved = rent_now;
+    pay_withdrawal(&withdraw_accounts, &campaign_data, surplus)?;
+    set_return_data(&surplus.to_le_bytes());
+
+    msg!("SURPLUS_WITHDRAWN amount={} campaign={}", surplus, writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ForkFundsRequest {
+    pub amount: u64,
+}
+/*----------------------------*/
+/*
+ * Move "amount" lamports of a campaign to another campaign of the program, to reorganize fundraising drives.
+ * accounts: [source writing_account, admin of the source, target writing_account], then the other signers of the source.
+ * The source keeps its rent_exemption + RENT_BUFFER_LAMPORTS and both amount_donated follow the lamports.
+ * The fork counts against the daily cap of the source and the target has to be active.
+ */
+fn fork_funds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: ForkFundsRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let AdminAccounts { writing_account: source_account, admin_account, other_accounts } = admin_accounts;
+    let target_account = next_account_info(&mut other_accounts.iter())?;
+
+    if target_account.owner != program_id {
+        msg!("The target campaign account must be owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if source_account.key == target_account.key {
+        msg!("Can't fork funds into the same campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut source_data = admin_accounts.load_campaign("fork the funds of the source campaign")?;
+    let mut target_data = load_campaign(target_account)?;
+    let now = current_clock(accounts)?.unix_timestamp;
+    check_payout(&source_data, now)?;
+    check_approvals(&source_data, admin_account, &other_accounts[1..])?;
+    /*
+     * the target takes the lamports as a donation, so it has to be able to take one.
+     */
+    ensure_active(&target_data)?;
+    if input_data.amount == 0 {
+        msg!("Nothing to fork!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    check_timelock(&source_data, input_data.amount)?;
+    if available_to_withdraw(source_account)? < input_data.amount {
+        msg!("Not enough balance to keep the source campaign alife!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+    record_daily_withdrawal(&mut source_data, input_data.amount, now)?;
+    source_data.amount_donated = source_data.amount_donated
+    .checked_sub(input_data.amount)
+    .ok_or_else(|| {
+        msg!("Can't fork more than the amount_donated of the source campaign!!!");
+        ProgramError::InsufficientFunds
+    })?;
+    target_data.amount_donated = target_data.amount_donated
+    .checked_add(input_data.amount)
+    .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    write_campaign(source_account, &source_data)?;
+    write_campaign(target_account, &target_data)?;
+    move_lamports(source_account, target_account, input_data.amount)?;
+
+    msg!(
+        "FUNDS_FORKED amount={} from={} to={}",
+        input_data.amount,
+        source_account.key,
+        target_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetTargetRequest {
+    pub target_amount: u64,
+}
+/*----------------------------*/
+/*
+ * Revise the goal of a campaign, it can't go below what was already donated.
+ * An all-or-nothing goal can only go up.
+ */
+fn set_target(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: SetTargetRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("change the target")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    /*
+     * the donors of an all-or-nothing campaign gave on the promise of its goal,
+     * a lower one would turn a failed campaign into a successful one.
+     */
+    if campaign_data.campaign_type == CampaignType::AllOrNothing as u8
+        && input_data.target_amount < campaign_data.target_amount
+    {
+        msg!("The target of an all-or-nothing campaign can't be lowered!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if input_data.target_amount < campaign_data.amount_donated {
+        msg!("The target can't be below the {} lamports already donated!!!", campaign_data.amount_donated);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if input_data.target_amount > MAX_TARGET_AMOUNT {
+        msg!("The target can't be more than {} lamports!!!", MAX_TARGET_AMOUNT);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    campaign_data.target_amount = input_data.target_amount;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("TARGET_SET target={} campaign={}", campaign_data.target_amount, writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct WithdrawSplitRequest {
+    /*
+     * lamports for each recipient account, in the order of the accounts.
+     */
+    pub amounts: Vec<u64>,
+}
+/*----------------------------*/
+/*
+ * Withdraw to several recipients at once (e.g. a charity and its overhead).
+ * Accounts: writing_account, admin (signer), platform_account, then one account per amount
+ * and the other signers of a campaign with required_signers.
+ * Every amount pays its own platform fee, like a withdraw of that amount.
+ */
+fn withdraw_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: WithdrawSplitRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let AdminAccounts { writing_account, admin_account, other_accounts } = admin_accounts;
+    let platform_account = next_account_info(&mut other_accounts.iter())?;
+    let mut campaign_data = admin_accounts.load_campaign("withdraw")?;
+    let now = current_clock(accounts)?.unix_timestamp;
+    check_payout(&campaign_data, now)?;
+    if !platform::check_id(platform_account.key) {
+        msg!("Wrong platform_account!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    /*
+     * one recipient account per amount after the platform_account, the other signers follow them.
+     */
+    let recipient_count = input_data.amounts.len();
+    if recipient_count == 0 || other_accounts.len() < 1 + recipient_count {
+        msg!("{} amounts for {} recipient accounts!!!", recipient_count, other_accounts.len() - 1);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (recipient_accounts, co_signer_accounts) = other_accounts[1..].split_at(recipient_count);
+    check_approvals(&campaign_data, admin_account, co_signer_accounts)?;
+    /*
+     * all the amounts together have to fit above the rent_exemption and RENT_BUFFER_LAMPORTS.
+     */
+    let total = input_data.amounts
+    .iter()
+    .try_fold(0u64, |total, amount| total.checked_add(*amount))
+    .ok_or(ProgramError::ArithmeticOverflow)?;
+    check_timelock(&campaign_data, total)?;
+    if available_to_withdraw(writing_account)? < total {
+        msg!("Not enough balance to keep the account alife!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+    record_daily_withdrawal(&mut campaign_data, total, now)?;
+    write_campaign(writing_account, &campaign_data)?;
+
+    for (recipient_account, amount) in recipient_accounts.iter().zip(&input_data.amounts) {
+        let fee = pay_out(writing_account, platform_account, recipient_account, *amount)?;
+
+        msg!(
+            "WITHDRAWAL amount={} fee={} beneficiary={} campaign={}",
+            amount,
+            fee,
+            recipient_account.key,
+            writing_account.key
+        );
+    }
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, Debug)]
+struct DonateRequest {
+    /*
+     * the donator, recorded as last_donor since the lamports come from the donator_program_account.
+     */
+    pub donor: Pubkey,
+    /*
+     * an anonymous donation updates the totals but doesn't record nor log the donor.
+     */
+    pub anonymous: bool,
+}
+/*
+ * Older clients send the donor only, a donation without the anonymous byte is anonymous.
+ */
+impl BorshDeserialize for DonateRequest {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let donor = Pubkey::deserialize(buf)?;
+        let anonymous = if buf.is_empty() {
+            true
+        } else {
+            bool::deserialize(buf)?
+        };
+        Ok(DonateRequest { donor, anonymous })
+    }
+}
+/*----------------------------*/
+/**************************************/
+/*
+ * Accounts of donate, in order: 0 writing_account, 1 donator_program_account, 2 donator.
+ * Every position is checked for what its account must be and the log names the position,
+ * so accounts passed in the wrong order are easy to spot.
+ */
+struct DonateAccounts<'a, 'info> {
+    /*
+     * the program-owned account containing the data of campaign we want to donate to.
+     */
+    writing_account: &'a AccountInfo<'info>,
+    /*
+     * also a program-owned account without data, it only has the Lamport we would like to donate.
+     */
+    donator_program_account: &'a AccountInfo<'info>,
+    /*
+     * the account of the donator.
+     */
+    donator: &'a AccountInfo<'info>,
+    /*
+     * the accounts only some campaigns need, in this order:
+     * the matcher_program_account when the campaign has a matcher, and the system program
+     * when a campaign with pool_fee_bps has no match pool yet,
+     * the donor_tally_account and the system program when the campaign has a max_donation_per_donor.
+     */
+    optional_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> DonateAccounts<'a, 'info> {
+    fn parse(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        check_accounts_len(accounts, &["writing_account", "donator_program_account", "donator (signer)"])?;
+        let accounts_iter = &mut accounts.iter();
+        let writing_account = next_account_info(accounts_iter)?;
+        let donator_program_account = next_account_info(accounts_iter)?;
+        let donator = next_account_info(accounts_iter)?;
+
+        if !donator.is_signer {
+            match accounts[..2].iter().position(|account| account.is_signer) {
+                Some(position) => msg!(
+                    "Account 2 (donator) is not a signer but account {} is, are the accounts out of order?!!!",
+                    position
+                ),
+                None => msg!("Account 2 (donator) is not a signer!!!"),
+            }
+            return Err(CrowdfundingError::NotSigner.into());
+        }
+        if writing_account.owner != program_id {
+            msg!("Account 0 (writing_account) is not owned by the program!!!");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if donator_program_account.owner != program_id {
+            msg!("Account 1 (donator_program_account) is not owned by the program!!!");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        /*
+         * both are owned by the program, a donator_program_account in position 0 holds no campaign.
+         */
+        if writing_account.data.borrow().iter().all(|byte| *byte == 0) {
+            msg!("Account 0 (writing_account) holds no campaign, is it the donator_program_account?!!!");
+            return Err(ProgramError::UninitializedAccount);
+        }
+        /*
+         * the donation moves lamports from one to the other, the same account twice would corrupt the accounting.
+         */
+        if donator_program_account.key == writing_account.key {
+            msg!("donator_program_account and writing_account must be different accounts!!!");
+            return Err(ProgramError::InvalidArgument);
+        }
+        /*
+         * the donation takes every lamport of the donator_program_account, an account holding data
+         * (another campaign, a registry, a tally...) is not one the donor created for it.
+         */
+        if donator_program_account.data_len() != 0 {
+            msg!("Account 1 (donator_program_account) holds data!!!");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(DonateAccounts { writing_account, donator_program_account, donator, optional_accounts: &accounts[3..] })
+    }
+}
+
+/*----------------------------*/
+/*
+ * The checks every donation entry point (donate, donate_direct, donate_split) makes before the campaign
+ * of the writing_account takes "offered" lamports from "donator".
+ * Returns the lamports it accepts: with cap_at_goal only what is left to reach its target_amount,
+ * the rest stays with the donor.
+ */
+fn accept_donation(
+    campaign_data: &CampaignDetails,
+    writing_account: &AccountInfo,
+    donator: &Pubkey,
+    offered: u64,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    ensure_active(campaign_data)?;
+    /*
+     * like withdraw, never let a writing_account below its rent_exemption take donations,
+     * the runtime could purge it with the donations.
+     */
+    available_above_rent(writing_account)?;
+    /*
+     * no more donations once the deadline has passed.
+     */
+    if is_expired(campaign_data, now) {
+        msg!("Campaign has ended");
+        return Err(CrowdfundingError::CampaignExpired.into());
+    }
+    /*
+     * the admin can't inflate a campaign that wants to show organic backing.
+     */
+    if campaign_data.block_self_donation && *donator == campaign_data.admin {
+        msg!("The admin can't donate to a campaign blocking self donations!!!");
+        return Err(CrowdfundingError::SelfDonation.into());
+    }
+    if offered < campaign_data.min_donation {
+        msg!("The donation is below the minimum of {} lamports!!!", campaign_data.min_donation);
+        return Err(CrowdfundingError::DonationTooSmall.into());
+    }
+    if !campaign_data.cap_at_goal {
+        return Ok(offered);
+    }
+    let remaining = remaining_to_goal(campaign_data);
+    if remaining == 0 {
+        msg!("Campaign already reached its goal!!!");
+        return Err(CrowdfundingError::GoalReached.into());
+    }
+    Ok(offered.min(remaining))
+}
+
+/*----------------------------*/
+/*
+ * Count an accepted donation in the campaign, "donated" is what the donor gave and "total" what the campaign gets
+ * (the matched lamports added, the pool fee taken off). "donor" is None for an anonymous donation.
+ * The caller writes the campaign and moves the lamports.
+ */
+fn record_donation(
+    campaign_data: &mut CampaignDetails,
+    campaign: &Pubkey,
+    donor: Option<&Pubkey>,
+    donated: u64,
+    total: u64,
+    now: i64,
+) -> ProgramResult {
+    /*
+     * a donation that would overflow the u64 total is rejected instead of wrapping around.
+     */
+    let was_overfunded = is_overfunded(campaign_data);
+    campaign_data.amount_donated = match campaign_data.amount_donated.checked_add(total) {
+        Some(total) => total,
+        None => {
+            msg!("amount_donated overflow!!!");
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    };
+    campaign_data.donor_count = match campaign_data.donor_count.checked_add(1) {
+        Some(count) => count,
+        None => {
+            msg!("donor_count overflow!!!");
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    };
+    if campaign_data.amount_donated >= campaign_data.target_amount {
+        msg!("Campaign goal reached!");
+        /*
+         * only the donation first reaching the goal sets it, later donations keep that time.
+         */
+        if campaign_data.goal_reached_at == 0 {
+            campaign_data.goal_reached_at = now;
+        }
+    }
+    if !was_overfunded && is_overfunded(campaign_data) {
+        msg!(
+            "CAMPAIGN_OVERFUNDED total={} target={} campaign={}",
+            campaign_data.amount_donated,
+            campaign_data.target_amount,
+            campaign
+        );
+    }
+    if let Some(donor) = donor {
+        campaign_data.last_donor = *donor;
+    }
+    /*
+     * the matched lamports don't count, the largest donation is what the donor gave.
+     */
+    if donated > campaign_data.largest_donation {
+        campaign_data.largest_donation = donated;
+        campaign_data.largest_donor = donor.copied().unwrap_or_default();
+    }
+    Ok(())
+}
+
+/**************************************/
+/*
+ * The donor_tally_account of a donor to a campaign with max_donation_per_donor and the tally it holds,
+ * with the system program when the first donation of the donor creates it.
+ */
+struct DonorTallyAccounts<'a, 'info> {
+    donor_tally_account: &'a AccountInfo<'info>,
+    system_program: Option<&'a AccountInfo<'info>>,
+    tally_bump: u8,
+    tally: DonorTally,
+}
+
+impl<'a, 'info> DonorTallyAccounts<'a, 'info> {
+    /*
+     * Take the donor_tally_account, and the system program when it doesn't exist yet, from the optional accounts
+     * of a donation. None when the campaign has no max_donation_per_donor.
+     */
+    fn parse(
+        program_id: &Pubkey,
+        campaign_data: &CampaignDetails,
+        campaign: &Pubkey,
+        donor: &Pubkey,
+        optional_accounts: &mut std::slice::Iter<'a, AccountInfo<'info>>,
+    ) -> Result<Option<Self>, ProgramError> {
+        if campaign_data.max_donation_per_donor == 0 {
+            return Ok(None);
+        }
+        let donor_tally_account = next_account_info(optional_accounts)?;
+        let (tally_address, tally_bump) = donor_tally_pda(program_id, campaign, donor);
+        if tally_address != *donor_tally_account.key {
+            msg!("donor_tally_account is not the donor tally address of this campaign and donor!!!");
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let (tally, system_program) = if donor_tally_account.owner == program_id {
+            (DonorTally::try_from_slice(&donor_tally_account.data.borrow())?, None)
+        } else {
+            (DonorTally::default(), Some(next_account_info(optional_accounts)?))
+        };
+        Ok(Some(DonorTallyAccounts { donor_tally_account, system_program, tally_bump, tally }))
+    }
+
+    /*
+     * Add a donation to the tally, one taking it above the max_donation_per_donor is refused.
+     */
+    fn add(&mut self, campaign_data: &CampaignDetails, donated: u64) -> ProgramResult {
+        let total_donated = self.tally.total_donated
+        .checked_add(donated)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+        if total_donated > campaign_data.max_donation_per_donor {
+            msg!(
+                "The donor would give {} lamports, the maximum per donor is {}!!!",
+                total_donated,
+                campaign_data.max_donation_per_donor
+            );
+            return Err(CrowdfundingError::DonorLimitExceeded.into());
+        }
+        self.tally.total_donated = total_donated;
+        Ok(())
+    }
+
+    /*
+     * Write the tally, on the first donation of the donor the program creates it and the donator pays its rent.
+     */
+    fn write(&self, program_id: &Pubkey, campaign: &Pubkey, donator: &AccountInfo<'info>) -> ProgramResult {
+        if self.donor_tally_account.owner != program_id {
+            let system_program = self.system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            create_pda_account(
+                program_id,
+                donator,
+                self.donor_tally_account,
+                system_program,
+                DonorTally::default().try_to_vec()?.len(),
+                &[DONOR_TALLY_SEED, campaign.as_ref(), donator.key.as_ref(), &[self.tally_bump]],
+            )?;
+        }
+        self.tally.serialize(&mut &mut self.donor_tally_account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+/*----------------------------*/
+/*
+ * We want to donate to a campaign, however we can't decrease the balance of an account not owned by our program in our program.
+ * This means we can't just transfer the balance as we did in the withdraw function. 
+ * Solana policies state: "An account not assigned to the program cannot have its balance decrease."
+ * So for this, we will create a program-owned account in our front-end and then perform the SOL token transaction.
+ */
+fn donate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: DonateRequest,
+) -> ProgramResult {
+
+    let DonateAccounts { writing_account, donator_program_account, donator, optional_accounts } =
+        DonateAccounts::parse(program_id, accounts)?;
+    let optional_accounts = &mut optional_accounts.iter();
+    if input_data.donor != *donator.key {
+        msg!("The donor in the instruction data is not the donator!!!");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    /*
+     * a donation drains the donator_program_account, an empty one was most likely
+     * already donated (the same donate processed twice) and must not count a donor again.
+     */
+    if **donator_program_account.lamports.borrow() == 0 {
+        msg!("Warning: donator_program_account is empty, was this donation already processed? Nothing to donate");
+        return Err(CrowdfundingError::NothingToDonate.into());
+    }
+    /*
+     * get the campaign_data and we will increment the amount_donated.
+     * What cap_at_goal doesn't accept stays in the donator_program_account.
+     */
+    let mut campaign_data = load_campaign(writing_account)?;
+    let now = current_clock(accounts)?.unix_timestamp;
+    let offered = **donator_program_account.lamports.borrow();
+    let donated = accept_donation(&campaign_data, writing_account, donator.key, offered, now)?;
+    /*
+     * while the match pool lasts the matcher adds as much as the donator,
+     * the matched lamports come from the matcher_program_account.
+     * pool_fee_bps of the donation goes the other way, into the matcher_program_account and the match pool.
+     */
+    let mut matcher_account = None;
+    let mut matched = 0;
+    let mut pool_fee = 0;
+    if campaign_data.matcher != Pubkey::default()
+        && (campaign_data.match_pool_remaining > 0 || campaign_data.pool_fee_bps > 0)
+    {
+        let account = next_account_info(optional_accounts)?;
+        let (matcher_address, matcher_bump) = matcher_pda(program_id, writing_account.key);
+        if *account.key != campaign_data.matcher || *account.key != matcher_address {
+            msg!("Wrong matcher_program_account!!!");
+            return Err(ProgramError::InvalidArgument);
+        }
+        /*
+         * no sponsor funded the match pool yet, the first donation paying a pool fee creates it.
+         * Only fund_match_pool fills match_pool_remaining and it creates the pool, so nothing is matched here.
+         */
+        let system_program = if account.owner == program_id {
+            None
+        } else if campaign_data.match_pool_remaining == 0 {
+            Some(next_account_info(optional_accounts)?)
+        } else {
+            msg!("Wrong matcher_program_account!!!");
+            return Err(ProgramError::InvalidArgument);
+        };
+        matched = compute_match(donated, campaign_data.match_pool_remaining);
+        pool_fee = (donated as u128 * campaign_data.pool_fee_bps as u128 / 10_000) as u64;
+        if campaign_data.cap_at_goal {
+            /*
+             * the matched lamports can't push the campaign past its goal either,
+             * donated is at most what remains so this can't underflow.
+             */
+            matched = matched.min(remaining_to_goal(&campaign_data) - (donated - pool_fee));
+        }
+        campaign_data.match_pool_remaining = campaign_data.match_pool_remaining
+        .checked_sub(matched)
+        .and_then(|pool| pool.checked_add(pool_fee))
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+        matcher_account = Some((account, system_program, matcher_bump));
+    }
+    /*
+     * a campaign with max_donation_per_donor tallies what each donor gave in a donor_tally_account,
+     * a donation taking the tally above the maximum is refused.
+     */
+    let mut donor_tally =
+        DonorTallyAccounts::parse(program_id, &campaign_data, writing_account.key, donator.key, optional_accounts)?;
+    if let Some(donor_tally) = donor_tally.as_mut() {
+        donor_tally.add(&campaign_data, donated)?;
+    }
+    let total_donated = (donated - pool_fee).checked_add(matched).ok_or(ProgramError::ArithmeticOverflow)?;
+    let donor = if input_data.anonymous { None } else { Some(&input_data.donor) };
+    record_donation(&mut campaign_data, writing_account.key, donor, donated, total_donated, now)?;
+    /*
+     * we write the new updated "campaign_data" to the writing_account's data field first,
+     * if that fails no lamport has moved yet and the record and the balance can't diverge.
+     */
+    write_campaign(writing_account, &campaign_data)?;
+    if let Some(donor_tally) = donor_tally {
+        donor_tally.write(program_id, writing_account.key, donator)?;
+    }
+
+    /*
+     * Then we do the actual transaction.
+     * Note that the donator_program_account is owned by program so it can decrease its Lamports.
+     * move_lamports releases each lamports borrow before taking the next one.
+     */
+    move_lamports(donator_program_account, writing_account, donated - pool_fee)?;
+    if let Some((matcher_program_account, system_program, matcher_bump)) = matcher_account {
+        if let Some(system_program) = system_program {
+            create_pda_account(
+                program_id,
+                donator,
+                matcher_program_account,
+                system_program,
+                0,
+                &[MATCHER_SEED, writing_account.key.as_ref(), &[matcher_bump]],
+            )?;
+        }
+        move_lamports(donator_program_account, matcher_program_account, pool_fee)?;
+        move_lamports(matcher_program_account, writing_account, matched)?;
+    }
+
+    if input_data.anonymous {
+        msg!(
+            "DONATION amount={} matched={} pool_fee={} total={} campaign={}",
+            donated,
+            matched,
+            pool_fee,
+            campaign_data.amount_donated,
+            writing_account.key
+        );
+    } else {
+        msg!(
+            "DONATION amount={} matched={} pool_fee={} total={} campaign={} donor={}",
+            donated,
+            matched,
+            pool_fee,
+            campaign_data.amount_donated,
+            writing_account.key,
+            input_data.donor
+        );
+    }
+    /*
+     * return data, 24 bytes: amount_donated, target_amount and the accepted donation (u64 little endian each),
+     * so a CPI caller gets the new total without reading the writing_account again.
+     * The accepted donation is below the donated lamports when cap_at_goal kept part of them.
+     */
+    let mut return_data = [0u8; 24];
+    return_data[..8].copy_from_slice(&campaign_data.amount_donated.to_le_bytes());
+    return_data[8..16].copy_from_slice(&campaign_data.target_amount.to_le_bytes());
+    return_data[16..].copy_from_slice(&donated.to_le_bytes());
+    set_return_data(&return_data);
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * donate, then write a DonationReceipt of it with "message" at the receipt_pda of the donor, campaign and current time.
+ * Accounts: the donate ones (writing_account, donator_program_account, donator), then the receipt_account,
+ * the system program and the optional accounts of donate the campaign needs.
+ * The donator pays the rent of the receipt, it names the donor even for an anonymous donation.
+ */
+fn donate_with_receipt(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: DonateRequest,
+    message: String,
+) -> ProgramResult {
+
+    check_accounts_len(
+        accounts,
+        &[
+            "writing_account",
+            "donator_program_account",
+            "donator (signer)",
+            "receipt_account",
+            "system_program",
+        ],
+    )?;
+    let (donate_accounts, receipt_accounts) = accounts.split_at(3);
+    let receipt_account = &receipt_accounts[0];
+    let system_program = &receipt_accounts[1];
+    let writing_account = &donate_accounts[0];
+    let donator_program_account = &donate_accounts[1];
+    let donator = &donate_accounts[2];
+
+    let timestamp = current_clock(accounts)?.unix_timestamp;
+    let (receipt_address, receipt_bump) = receipt_pda(program_id, donator.key, writing_account.key, timestamp);
+    if receipt_address != *receipt_account.key {
+        msg!("receipt_account is not the receipt address of this donor, campaign and time!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if receipt_account.owner == program_id && receipt_account.data.borrow().iter().any(|byte| *byte != 0) {
+        msg!("The receipt already exists!!!");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let before = donator_program_account.lamports();
+    let mut donate_accounts = donate_accounts.to_vec();
+    donate_accounts.extend(receipt_accounts[2..].iter().cloned());
+    donate(program_id, &donate_accounts, input_data)?;
+    let receipt = DonationReceipt {
+        campaign: *writing_account.key,
+        donor: *donator.key,
+        amount: before - donator_program_account.lamports(),
+        timestamp,
+        message,
+    };
+
+    if receipt_account.owner != program_id {
+        create_pda_account(
+            program_id,
+            donator,
+            receipt_account,
+            system_program,
+            receipt.try_to_vec()?.len(),
+            &[
+                RECEIPT_SEED,
+                donator.key.as_ref(),
+                writing_account.key.as_ref(),
+                &timestamp.to_le_bytes(),
+                &[receipt_bump],
+            ],
+        )?;
+    }
+    receipt.serialize(&mut &mut receipt_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "RECEIPT amount={} campaign={} receipt={}",
+        receipt.amount,
+        writing_account.key,
+        receipt_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DonateWithMessageRequest {
+    pub donor: Pubkey,
+    pub anonymous: bool,
+    pub message: String,
+}
+/*----------------------------*/
+/*
+ * donate_with_receipt with a short message of the donor, so campaigns can show a feed of them.
+ * Same accounts as donate_with_receipt.
+ */
+fn donate_with_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: DonateWithMessageRequest,
+) -> ProgramResult {
+    if input_data.message.len() > MAX_MESSAGE_LEN {
+        msg!("message is longer than {} bytes!!!", MAX_MESSAGE_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let donate_request = DonateRequest { donor: input_data.donor, anonymous: input_data.anonymous };
+    donate_with_receipt(program_id, accounts, donate_request, input_data.message)
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct UpdateRequest {
+    pub name: String,
+    pub description: String,
+    pub image_link: String,
+}
+/*----------------------------*/
+/*
+ * Let the admin fix the name, description or image_link of a campaign,
+ * everything else stored in the campaign (amount_donated, target_amount, ...) is preserved.
+ */
+fn update_campain(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: UpdateRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("update the campaign")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+    check_text_lengths(&input_data.name, &input_data.description, &input_data.image_link)?;
+    check_image_link(&input_data.image_link)?;
+
+    campaign_data.name = input_data.name;
+    campaign_data.description = input_data.description;
+    campaign_data.image_link = input_data.image_link;
+
+    /*
+     * the account can't grow, so the new strings must fit in the space allocated at creation.
+     */
+    write_campaign(writing_account, &campaign_data)?;
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct RefundRequest {
+    pub amount: u64,
+}
+/*----------------------------*/
+/*
+ * Send lamports back from a failed all-or-nothing campaign (or one with refunds_enabled) to a donor.
+ * Per donor balances are not stored on chain, so the admin has to sign and decide the amount.
+ * Any other campaign pays out through the withdraw instructions, with their time lock and daily cap.
+ * Accounts: writing_account, admin (signer), donor, then the other signers of a campaign with required_signers.
+ */
+fn refund(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: RefundRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let AdminAccounts { writing_account, admin_account, other_accounts } = admin_accounts;
+    let donor_account = next_account_info(&mut other_accounts.iter())?;
+    let mut campaign_data = admin_accounts.load_campaign("refund")?;
+    /*
+     * a flagged campaign's funds are frozen pending investigation, refunds included.
+     */
+    if campaign_data.status == CampaignStatus::Flagged as u8 {
+        msg!("Campaign is flagged, its funds are frozen!!!");
+        return Err(CrowdfundingError::CampaignFlagged.into());
+    }
+    check_approvals(&campaign_data, admin_account, &other_accounts[1..])?;
+    /*
+     * once an all-or-nothing campaign failed the lamports belong to the donors, none can go to the admin or beneficiary.
+     */
+    if !is_failed(&campaign_data, current_clock(accounts)?.unix_timestamp) && !campaign_data.refunds_enabled {
+        msg!("Only a failed all-or-nothing campaign can refund, the others withdraw!!!");
+        return Err(CrowdfundingError::RefundsNotAllowed.into());
+    }
+    if *donor_account.key == campaign_data.admin || *donor_account.key == campaign_data.beneficiary {
+        msg!("Refunds can't be sent to the admin or the beneficiary!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    /*
+     * a refund can't give back more than was donated.
+     */
+    campaign_data.amount_donated = match campaign_data.amount_donated.checked_sub(input_data.amount) {
+        Some(total) => total,
+        None => {
+            msg!("Refund is bigger than amount_donated!!!");
+            return Err(ProgramError::InsufficientFunds);
+        }
+    };
+    if available_above_rent(writing_account)? < input_data.amount {
+        msg!("Not enough balance to keep the account alife!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+
+    move_lamports(writing_account, donor_account, input_data.amount)?;
+    write_campaign(writing_account, &campaign_data)?;
+
+    Ok(())
+}
+/**************************************/
+/*
+ * One campaign less in the AdminRegistry of "admin", so it can create another one.
+ * The registry counts the campaigns that are not Closed, end_campaign releases the slot
+ * and close_campaign only does when the campaign wasn't ended first.
+ */
+fn release_campaign_slot(program_id: &Pubkey, registry_account: &AccountInfo, admin: &Pubkey) -> ProgramResult {
+    if admin_registry_pda(program_id, admin).0 != *registry_account.key || registry_account.owner != program_id {
+        msg!("registry_account is not the admin registry address of this admin!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mut registry = AdminRegistry::try_from_slice(&registry_account.data.borrow())?;
+    registry.campaign_count = registry.campaign_count.saturating_sub(1);
+    registry.serialize(&mut &mut registry_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Shut down a finished campaign, the data is zeroed so the account is purged by the runtime
+ * and the admin gets back the rent_exemption and RENT_BUFFER_LAMPORTS left in the writing_account.
+ * The donations only leave through the withdraw instructions (to the beneficiary, with the platform fee)
+ * or refund, so everything above has to be withdrawn or refunded first.
+ * Accounts: writing_account, admin (signer), the AdminRegistry of the admin,
+ * then the other signers of a campaign with required_signers.
+ */
+fn close_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let campaign_data = admin_accounts.load_campaign("close the campaign")?;
+    let AdminAccounts { writing_account, admin_account, other_accounts } = admin_accounts;
+    let registry_account = next_account_info(&mut other_accounts.iter())?;
+    /*
+     * closing would hand the funds of a flagged campaign out of the freeze.
+     */
+    if campaign_data.status == CampaignStatus::Flagged as u8 {
+        msg!("Campaign is flagged, its funds are frozen!!!");
+        return Err(CrowdfundingError::CampaignFlagged.into());
+    }
+    check_approvals(&campaign_data, admin_account, &other_accounts[1..])?;
+    /*
+     * donors can still send funds until the deadline or until the admin ends the campaign,
+     * so the campaign can't be closed before. A campaign without deadline has to be ended first.
+     */
+    let now = current_clock(accounts)?.unix_timestamp;
+    let ended = campaign_data.status == CampaignStatus::Closed as u8;
+    if !ended && !is_expired(&campaign_data, now) {
+        msg!("The campaign is still running!!!");
+        return Err(CrowdfundingError::CampaignStillActive.into());
+    }
+    /*
+     * a failed all-or-nothing campaign refunds every donation before it can be closed.
+     */
+    if (is_failed(&campaign_data, now) || campaign_data.refunds_enabled) && campaign_data.amount_donated > 0 {
+        msg!("{} lamports still have to be refunded!!!", campaign_data.amount_donated);
+        return Err(CrowdfundingError::GoalNotReached.into());
+    }
+    let to_withdraw = available_to_withdraw(writing_account)?;
+    if to_withdraw > 0 {
+        msg!("{} lamports still have to be withdrawn or refunded!!!", to_withdraw);
+        return Err(CrowdfundingError::FundsRemaining.into());
+    }
+    if !ended {
+        release_campaign_slot(program_id, registry_account, &campaign_data.admin)?;
+    }
+
+    let remaining = writing_account.lamports();
+    move_lamports(writing_account, admin_account, remaining)?;
+    writing_account.try_borrow_mut_data()?.fill(0);
+
+    msg!(
+        "CAMPAIGN_CLOSED amount={} campaign={}",
+        remaining,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct TransferAdminRequest {
+    pub new_admin: Pubkey,
+}
+/*----------------------------*/
+/*
+ * Hand the campaign over to another admin, only the current admin can do it.
+ * Accounts: writing_account, admin (signer), the AdminRegistry of the admin, the AdminRegistry of the new admin
+ * and the system program when the new admin has none yet (the admin pays its rent).
+ * The campaign moves from one registry count to the other, unless it is Closed.
+ */
+fn transfer_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: TransferAdminRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("transfer the campaign")?;
+    let AdminAccounts { writing_account, admin_account, other_accounts } = admin_accounts;
+    let accounts_iter = &mut other_accounts.iter();
+    let registry_account = next_account_info(accounts_iter)?;
+    let new_registry_account = next_account_info(accounts_iter)?;
+    let system_program = accounts_iter.next();
+
+    if input_data.new_admin == campaign_data.admin {
+        msg!("The new admin is already the admin of the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if campaign_data.status != CampaignStatus::Closed as u8 {
+        let mut new_registry = load_admin_registry(
+            program_id,
+            &input_data.new_admin,
+            new_registry_account,
+            admin_account,
+            system_program,
+        )?;
+        if new_registry.campaign_count >= MAX_CAMPAIGNS_PER_ADMIN {
+            msg!("The new admin already has {} campaigns!!!", MAX_CAMPAIGNS_PER_ADMIN);
+            return Err(CrowdfundingError::TooManyCampaigns.into());
+        }
+        new_registry.campaign_count += 1;
+        release_campaign_slot(program_id, registry_account, &campaign_data.admin)?;
+        new_registry.serialize(&mut &mut new_registry_account.data.borrow_mut()[..])?;
+    }
+
+    campaign_data.admin = input_data.new_admin;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!(
+        "ADMIN_TRANSFERRED new_admin={} campaign={}",
+        campaign_data.admin,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+/*
+ * Layout of the campaign accounts created before the version byte was added.
+ */
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct CampaignDetailsV0 {
+    pub admin: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub image_link: String,
+    pub amount_donated: u64,
+}
+/*----------------------------*/
+/*
+ * Convert a v0 account into the current layout, the fields v0 didn't have get a value
+ * that keeps the old behaviour (no goal, no deadline, no minimum donation).
+ * v0 accounts were allocated with the exact size of their data, so every byte must be read.
+ */
+fn migrate_v0(data: &[u8]) -> Result<CampaignDetails, ProgramError> {
+    let old = CampaignDetailsV0::try_from_slice(data).map_err(|_| {
+        msg!("writing_account is not a v0 campaign!!!");
+        ProgramError::InvalidAccountData
+    })?;
+    Ok(CampaignDetails {
+        version: CAMPAIGN_VERSION,
+        admin: old.admin,
+        name: old.name,
+        description: old.description,
+        image_link: old.image_link,
+        amount_donated: old.amount_donated,
+        target_amount: 0,
+        deadline: 0,
+        donor_count: 0,
+        min_donation: 0,
+        status: CampaignStatus::Active as u8,
+        category: CampaignCategory::Other as u8,
+        beneficiary: old.admin,
+        campaign_type: CampaignType::Flexible as u8,
+        last_donor: Pubkey::default(),
+        mint: Pubkey::default(),
+        token_amount_donated: 0,
+        matcher: Pubkey::default(),
+        match_pool_remaining: 0,
+        max_withdraw_per_day: 0,
+        withdrawn_today: 0,
+        last_withdraw_ts: 0,
+        created_at: 0,
+        largest_donation: 0,
+        largest_donor: Pubkey::default(),
+        pool_fee_bps: 0,
+        next_withdraw_ts: 0,
+        interval_seconds: 0,
+        per_interval_amount: 0,
+        metadata_account: Pubkey::default(),
+        refunds_enabled: false,
+        required_signers: 0,
+        signers: Vec::new(),
+        cap_at_goal: false,
+        block_self_donation: false,
+        pending_withdraw_amount: 0,
+        withdraw_unlock_ts: 0,
+        max_donation_per_donor: 0,
+        goal_reached_at: 0,
+        rent_reserved: 0,
+        timelock_threshold: 0,
+        seed_admin: Pubkey::default(),
+        seed_name: String::new(),
+        bump: 0,
+    })
+}
+/*----------------------------*/
+/*
+ * Upgrade a v0 campaign account to CAMPAIGN_VERSION, only the admin can do it.
+ * The account grows, so it must hold enough lamports for the new rent_exemption,
+ * the admin can top it up with a plain system transfer before migrating.
+ */
+fn migrate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let AdminAccounts { writing_account, admin_account, .. } = AdminAccounts::parse(program_id, accounts)?;
+    /*
+     * a v0 account doesn't load as a campaign, its admin is checked on the migrated one.
+     */
+    let mut campaign_data = migrate_v0(&writing_account.data.borrow())?;
+
+    if campaign_data.admin != *admin_account.key {
+        msg!("Only the account admin can migrate the campaign");
+        return Err(CrowdfundingError::WrongAdmin.into());
+    }
+
+    if writing_account.data_len() < campaign_account_size() {
+        writing_account.realloc(campaign_account_size(), false)?;
+    }
+    campaign_data.rent_reserved = rent_exemption(writing_account.data_len())?;
+    if writing_account.lamports() < campaign_data.rent_reserved {
+        msg!("The balance of writing_account is less than the rent_exemption ammount!!!");
+        return Err(CrowdfundingError::InsufficientFundsForRent.into());
+    }
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!(
+        "CAMPAIGN_MIGRATED version={} campaign={}",
+        CAMPAIGN_VERSION,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+/*
+ * Stop a campaign from accepting donations, e.g. once the admin withdrew everything.
+ * Unlike close_campaign the account and its data stay on chain.
+ * Accounts: writing_account, admin (signer), the AdminRegistry of the admin which gets the campaign slot back.
+ */
+fn end_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("end the campaign")?;
+    let AdminAccounts { writing_account, other_accounts, .. } = admin_accounts;
+    let registry_account = next_account_info(&mut other_accounts.iter())?;
+
+    if campaign_data.status == CampaignStatus::Flagged as u8 {
+        msg!("A flagged campaign stays flagged until the platform unflags it!!!");
+        return Err(CrowdfundingError::CampaignFlagged.into());
+    }
+    if campaign_data.status != CampaignStatus::Closed as u8 {
+        release_campaign_slot(program_id, registry_account, &campaign_data.admin)?;
+    }
+    campaign_data.status = CampaignStatus::Closed as u8;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("CAMPAIGN_ENDED campaign={}", writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, Debug)]
+struct DonateSplRequest {
+    pub amount: u64,
+    /*
+     * like donate, an anonymous donation updates the totals but doesn't record nor log the donor.
+     */
+    pub anonymous: bool,
+}
+/*
+ * Older clients send the amount only, their donations keep recording the donor.
+ */
+impl BorshDeserialize for DonateSplRequest {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let amount = u64::deserialize(buf)?;
+        let anonymous = if buf.is_empty() {
+            false
+        } else {
+            bool::deserialize(buf)?
+        };
+        Ok(DonateSplRequest { amount, anonymous })
+    }
+}
+/*----------------------------*/
+/*
+ * Donate SPL tokens instead of lamports, with the checks of accept_donation on the token amount.
+ * The donator signs a token transfer (CPI to the token program) from its own token account
+ * to the campaign token account, a token account of the campaign mint owned by the writing_account.
+ */
+fn donate_spl(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: DonateSplRequest,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donator_token_account = next_account_info(accounts_iter)?;
+    let campaign_token_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !donator.is_signer {
+        msg!("The donator is not a signer!!!");
+        return Err(CrowdfundingError::NotSigner.into());
+    }
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if *token_program.key != spl_token::id() || *campaign_token_account.owner != spl_token::id() {
+        msg!("Wrong token program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if input_data.amount == 0 {
+        msg!("Nothing to donate!!!");
+        return Err(CrowdfundingError::NothingToDonate.into());
+    }
+    let mut campaign_data = load_campaign(writing_account)?;
+    let now = current_clock(accounts)?.unix_timestamp;
+    let donated = accept_donation(&campaign_data, writing_account, donator.key, input_data.amount, now)?;
+    /*
+     * the tokens have to land in a token account of the campaign mint that the campaign controls.
+     */
+    let campaign_tokens = spl_token::state::Account::unpack(&campaign_token_account.data.borrow())?;
+    if campaign_data.mint == Pubkey::default() || campaign_tokens.mint != campaign_data.mint {
+        msg!("campaign_token_account is not for the campaign mint!!!");
+        return Err(CrowdfundingError::WrongMint.into());
+    }
+    if campaign_tokens.owner != *writing_account.key {
+        msg!("campaign_token_account is not owned by the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    campaign_data.token_amount_donated = match campaign_data.token_amount_donated.checked_add(donated) {
+        Some(total) => total,
+        None => {
+            msg!("token_amount_donated overflow!!!");
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    };
+    campaign_data.donor_count = match campaign_data.donor_count.checked_add(1) {
+        Some(count) => count,
+        None => {
+            msg!("donor_count overflow!!!");
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    };
+    if !input_data.anonymous {
+        campaign_data.last_donor = *donator.key;
+    }
+    /*
+     * like donate, the campaign is written before the tokens move.
+     */
+    write_campaign(writing_account, &campaign_data)?;
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            donator_token_account.key,
+            campaign_token_account.key,
+            donator.key,
+            &[],
+            donated,
+        )?,
+        &[
+            donator_token_account.clone(),
+            campaign_token_account.clone(),
+            donator.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    if input_data.anonymous {
+        msg!(
+            "TOKEN_DONATION amount={} total={} mint={} campaign={}",
+            donated,
+            campaign_data.token_amount_donated,
+            campaign_data.mint,
+            writing_account.key
+        );
+    } else {
+        msg!(
+            "TOKEN_DONATION amount={} total={} mint={} campaign={} donor={}",
+            donated,
+            campaign_data.token_amount_donated,
+            campaign_data.mint,
+            writing_account.key,
+            donator.key
+        );
+    }
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Make sure the stored seeds of the campaign still derive writing_account before it signs with them.
+ * Campaigns created before the seeds were stored take their current admin and name.
+ */
+fn check_signer_seeds(
+    program_id: &Pubkey,
+    writing_account: &AccountInfo,
+    mut campaign_data: CampaignDetails,
+) -> Result<CampaignDetails, ProgramError> {
+    if campaign_data.seed_admin == Pubkey::default() {
+        campaign_data.bump = campaign_pda(program_id, &campaign_data.admin, &campaign_data.name).1;
+        campaign_data.seed_admin = campaign_data.admin;
+        campaign_data.seed_name = campaign_data.name.clone();
+    }
+    if Pubkey::create_program_address(&campaign_signer_seeds(&campaign_data), program_id) != Ok(*writing_account.key) {
+        msg!("writing_account is not the campaign address of its creation seeds!!!");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(campaign_data)
+}
+/*----------------------------*/
+/*
+ * Tokens of another mint than the campaign mint sent to a token account of the campaign by mistake.
+ * The admin moves all of them to a recovery token account, the campaign signs for its token account.
+ * accounts: [writing_account, admin, stray token account, recovery token account, token program].
+ */
+fn sweep_wrong_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let AdminAccounts { writing_account, other_accounts, .. } = admin_accounts;
+    let accounts_iter = &mut other_accounts.iter();
+    let stray_token_account = next_account_info(accounts_iter)?;
+    let recovery_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let campaign_data = admin_accounts.load_campaign("sweep tokens")?;
+    if *token_program.key != spl_token::id() || *stray_token_account.owner != spl_token::id() {
+        msg!("Wrong token program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let stray_tokens = spl_token::state::Account::unpack(&stray_token_account.data.borrow())?;
+    if stray_tokens.mint == campaign_data.mint {
+        msg!("The tokens are of the campaign mint, they are donations!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if stray_tokens.owner != *writing_account.key {
+        msg!("The stray token account is not owned by the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    /*
+     * the campaign signs as the owner of its token account, with the seeds of its address.
+     */
+    let campaign_data = check_signer_seeds(program_id, writing_account, campaign_data)?;
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            stray_token_account.key,
+            recovery_token_account.key,
+            writing_account.key,
+            &[],
+            stray_tokens.amount,
+        )?,
+        &[
+            stray_token_account.clone(),
+            recovery_token_account.clone(),
+            writing_account.clone(),
+            token_program.clone(),
+        ],
+        &[&campaign_signer_seeds(&campaign_data)],
+    )?;
+
+    msg!(
+        "WRONG_MINT_SWEPT amount={} mint={} recovery={} campaign={}",
+        stray_tokens.amount,
+        stray_tokens.mint,
+        recovery_token_account.key,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/*----------------------------*/
+/*
+ * Pay "amount" donated tokens to the token account of the beneficiary, with the admin, signer,
+ * all-or-nothing and Flagged checks of withdraw. The campaign signs for its token account.
+ * accounts: [writing_account, admin, campaign token account, beneficiary token account, token program],
+ * then the other signers of a campaign with required_signers.
+ */
+fn withdraw_spl(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: WithdrawRequest,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let AdminAccounts { writing_account, admin_account, other_accounts } = admin_accounts;
+    let accounts_iter = &mut other_accounts.iter();
+    let campaign_token_account = next_account_info(accounts_iter)?;
+    let beneficiary_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let co_signer_accounts = &other_accounts[3..];
+    let campaign_data = admin_accounts.load_campaign("withdraw tokens")?;
+    check_payout(&campaign_data, current_clock(accounts)?.unix_timestamp)?;
+    check_approvals(&campaign_data, admin_account, co_signer_accounts)?;
+    if *token_program.key != spl_token::id()
+        || *campaign_token_account.owner != spl_token::id()
+        || *beneficiary_token_account.owner != spl_token::id()
+    {
+        msg!("Wrong token program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let campaign_tokens = spl_token::state::Account::unpack(&campaign_token_account.data.borrow())?;
+    if campaign_data.mint == Pubkey::default() || campaign_tokens.mint != campaign_data.mint {
+        msg!("campaign_token_account is not for the campaign mint!!!");
+        return Err(CrowdfundingError::WrongMint.into());
+    }
+    if campaign_tokens.owner != *writing_account.key {
+        msg!("campaign_token_account is not owned by the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let beneficiary_tokens = spl_token::state::Account::unpack(&beneficiary_token_account.data.borrow())?;
+    if beneficiary_tokens.mint != campaign_data.mint || beneficiary_tokens.owner != campaign_data.beneficiary {
+        msg!("beneficiary_token_account is not a token account of the beneficiary for the campaign mint!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if input_data.amount == 0 || input_data.amount > campaign_tokens.amount {
+        msg!("Only {} tokens can be withdrawn!!!", campaign_tokens.amount);
+        return Err(ProgramError::InsufficientFunds);
+    }
+    let campaign_data = check_signer_seeds(program_id, writing_account, campaign_data)?;
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            campaign_token_account.key,
+            beneficiary_token_account.key,
+            writing_account.key,
+            &[],
+            input_data.amount,
+        )?,
+        &[
+            campaign_token_account.clone(),
+            beneficiary_token_account.clone(),
+            writing_account.clone(),
+            token_program.clone(),
+        ],
+        &[&campaign_signer_seeds(&campaign_data)],
+    )?;
+
+    msg!(
+        "TOKEN_WITHDRAWAL amount={} mint={} beneficiary={} campaign={}",
+        input_data.amount,
+        campaign_data.mint,
+        beneficiary_token_account.key,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+/*
+ * Top up the match pool of an active campaign.
+ * Like a donation, the sponsor funds a program-owned account without data in the front-end,
+ * its lamports move to the matcher_program_account and are added to match_pool_remaining.
+ * Accounts: writing_account, sponsor_program_account, matcher_program_account (the matcher_pda of the campaign),
+ * sponsor (signer), then the system program on the first top up, which creates the match pool.
+ */
+fn fund_match_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let sponsor_program_account = next_account_info(accounts_iter)?;
+    let matcher_program_account = next_account_info(accounts_iter)?;
+    let sponsor = next_account_info(accounts_iter)?;
+
+    if !sponsor.is_signer {
+        msg!("The sponsor is not a signer!!!");
+        return Err(CrowdfundingError::NotSigner.into());
+    }
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if sponsor_program_account.owner != program_id {
+        msg!("sponsor_program_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    /*
+     * like the donator_program_account of donate, every lamport of it moves, so it can't be an account holding data.
+     */
+    if sponsor_program_account.data_len() != 0 {
+        msg!("sponsor_program_account holds data!!!");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut campaign_data = load_campaign(writing_account)?;
+    let (matcher_address, matcher_bump) = matcher_pda(program_id, writing_account.key);
+    if campaign_data.matcher == Pubkey::default()
+        || *matcher_program_account.key != campaign_data.matcher
+        || *matcher_program_account.key != matcher_address
+    {
+        msg!("matcher_program_account is not the matcher of the campaign!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if sponsor_program_account.key == matcher_program_account.key {
+        msg!("sponsor_program_account and matcher_program_account must be different accounts!!!");
+        return Err(ProgramError::InvalidArgument);
+    }
+    /*
+     * the pool of a closed, flagged or paused campaign matches no donation.
+     */
+    ensure_active(&campaign_data)?;
+    if is_expired(&campaign_data, current_clock(accounts)?.unix_timestamp) {
+        msg!("Campaign has ended");
+        return Err(CrowdfundingError::CampaignExpired.into());
+    }
+
+    let funded = sponsor_program_account.lamports();
+    if funded == 0 {
+        msg!("Nothing to add to the match pool");
+        return Err(CrowdfundingError::NothingToDonate.into());
+    }
+    campaign_data.match_pool_remaining = match campaign_data.match_pool_remaining.checked_add(funded) {
+        Some(pool) => pool,
+        None => {
+            msg!("match_pool_remaining overflow!!!");
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    };
+    /*
+     * first top up, the program creates the match pool so donate can move the lamports out of it.
+     */
+    if matcher_program_account.owner != program_id {
+        let system_program = next_account_info(accounts_iter)?;
+        create_pda_account(
+            program_id,
+            sponsor,
+            matcher_program_account,
+            system_program,
+            0,
+            &[MATCHER_SEED, writing_account.key.as_ref(), &[matcher_bump]],
+        )?;
+    }
+    move_lamports(sponsor_program_account, matcher_program_account, funded)?;
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!(
+        "MATCH_POOL_FUNDED amount={} pool={} campaign={}",
+        funded,
+        campaign_data.match_pool_remaining,
+        writing_account.key
+    );
+
+    Ok(())
+}
+/**************************************/
+/*
+ * Pause or unpause a campaign, while paused donations are refused (e.g. during a dispute),
+ * the campaign keeps its funds and can be unpaused by the admin at any time.
+ */
+fn set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+) -> ProgramResult {
+
+    let admin_accounts = AdminAccounts::parse(program_id, accounts)?;
+    let mut campaign_data = admin_accounts.load_campaign("pause or unpause the campaign")?;
+    let AdminAccounts { writing_account, .. } = admin_accounts;
+
+    /*
+     * only an Active campaign can be paused and only a Paused one unpaused, doing it again changes nothing.
+     */
+    match CampaignStatus::from_u8(campaign_data.status) {
+        Some(CampaignStatus::Active) | Some(CampaignStatus::Paused) => {}
+        _ => return ensure_active(&campaign_data),
+    }
+    campaign_data.status = if paused {
+        CampaignStatus::Paused as u8
+    } else {
+        CampaignStatus::Active as u8
+    };
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("CAMPAIGN_PAUSED paused={} campaign={}", paused, writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct MinimalCampaignRequest {
+    pub name: String,
+    pub admin: Pubkey,
+}
+/*----------------------------*/
+/*
+ * Create a campaign from just a name and an admin, for campaigns created by bots.
+ * The description and image_link are empty, there is no goal and no deadline,
+ * the accounts are the same as for create_campain.
+ */
+fn create_campain_minimal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: MinimalCampaignRequest,
+) -> ProgramResult {
+    let campaign_data = CampaignDetails {
+        version: CAMPAIGN_VERSION,
+        admin: input_data.admin,
+        name: input_data.name,
+        description: String::new(),
+        image_link: String::new(),
+        amount_donated: 0,
+        target_amount: 0,
+        deadline: 0,
+        donor_count: 0,
+        min_donation: 0,
+        status: CampaignStatus::Active as u8,
+        category: CampaignCategory::Other as u8,
+        beneficiary: input_data.admin,
+        campaign_type: CampaignType::Flexible as u8,
+        last_donor: Pubkey::default(),
+        mint: Pubkey::default(),
+        token_amount_donated: 0,
+        matcher: Pubkey::default(),
+        match_pool_remaining: 0,
+        max_withdraw_per_day: 0,
+        withdrawn_today: 0,
+        last_withdraw_ts: 0,
+        created_at: 0,
+        largest_donation: 0,
+        largest_donor: Pubkey::default(),
+        pool_fee_bps: 0,
+        next_withdraw_ts: 0,
+        interval_seconds: 0,
+        per_interval_amount: 0,
+        metadata_account: Pubkey::default(),
+        refunds_enabled: false,
+        required_signers: 0,
+        signers: Vec::new(),
+        cap_at_goal: false,
+        block_self_donation: false,
+        pending_withdraw_amount: 0,
+        withdraw_unlock_ts: 0,
+        max_donation_per_donor: 0,
+        goal_reached_at: 0,
+        rent_reserved: 0,
+        timelock_threshold: 0,
+        seed_admin: Pubkey::default(),
+        seed_name: String::new(),
+        bump: 0,
+    };
+    create_campain(program_id, accounts, campaign_data)
+}
+/**************************************/
+/*
+ * Flag or unflag a campaign suspected to be a scam, only the platform can do it.
+ * The record is kept, front-ends hide flagged campaigns and donations are refused.
+ */
+fn set_flagged(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    flagged: bool,
+) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let platform_account = next_account_info(accounts_iter)?;
+
+    if !platform_account.is_signer {
+        msg!("The platform_account is not a signer!!!");
+        return Err(CrowdfundingError::NotSigner.into());
+    }
+    if !platform::check_id(platform_account.key) {
+        msg!("Only the platform can flag or unflag a campaign");
+        return Err(CrowdfundingError::WrongAdmin.into());
+    }
+    if writing_account.owner != program_id {
+        msg!("writing_account not owned by the program!!!");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut campaign_data = load_campaign(writing_account)?;
+
+    /*
+     * flagging replaces the Active or Paused status and unflagging makes the campaign Active again,
+     * a Closed campaign stays closed.
+     */
+    if campaign_data.status == CampaignStatus::Closed as u8 {
+        msg!("Campaign is closed");
+        return Err(CrowdfundingError::CampaignClosed.into());
+    }
+    if flagged {
+        campaign_data.status = CampaignStatus::Flagged as u8;
+    } else if campaign_data.status == CampaignStatus::Flagged as u8 {
+        campaign_data.status = CampaignStatus::Active as u8;
+    }
+    write_campaign(writing_account, &campaign_data)?;
+
+    msg!("CAMPAIGN_FLAGGED flagged={} campaign={}", flagged, writing_account.key);
+
+    Ok(())
+}
+/**************************************/
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DonateSplitRequest {
+    /*
+     * one weight per campaign, the donation is split proportionally to them,
+     * empty to split it evenly.
+     */
+    pub weights: Vec<u64>,
+}
+/*----------------------------*/
+/*
+ * Split one donation across several campaigns ("donate to all campaigns in this category").
+ * Accounts: the donate ones for the first campaign (writing_account, donator_program_account, donator (signer))
+ * and the donor_tally_account and system program it may need, then the writing_account of every other campaign
+ * receiving a share, each followed by the same optional accounts for its campaign.
+ * The remainder of an uneven division goes to the first campaign. Split donations are anonymous and not matched,
+ * what cap_at_goal doesn't accept of a share stays in the donator_program_account.
+ */
+fn donate_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_data: DonateSplitRequest,
+) -> ProgramResult {
+
+    let DonateAccounts { writing_account, donator_program_account, donator, optional_accounts } =
+        DonateAccounts::parse(program_id, accounts)?;
+    let optional_accounts = &mut optional_accounts.iter();
+    /*
+     * every campaign with its donor tally, a clock sysvar appended by the client is not a campaign.
+     */
+    let mut campaigns = Vec::new();
+    let mut next_writing_account = Some(writing_account);
+    while let Some(writing_account) = next_writing_account {
+        if writing_account.owner != program_id {
+            msg!("writing_account not owned by the program!!!");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let campaign_data = load_campaign(writing_account)?;
+        let donor_tally =
+            DonorTallyAccounts::parse(program_id, &campaign_data, writing_account.key, donator.key, optional_accounts)?;
+        campaigns.push((writing_account, campaign_data, donor_tally));
+        next_writing_account = optional_accounts.find(|account| !sysvar::clock::check_id(account.key));
+    }
+    /*
+     * a campaign listed twice would only keep the accounting of its last share.
+     */
+    for (position, (writing_account, ..)) in campaigns.iter().enumerate() {
+        if campaigns[..position].iter().any(|(listed, ..)| listed.key == writing_account.key) {
+            msg!("Campaign {} is listed twice!!!", writing_account.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    let weights = if input_data.weights.is_empty() {
+        vec![1; campaigns.len()]
+    } else {
+        input_data.weights
+    };
+    if weights.len() != campaigns.len() {
+        msg!("{} weights for {} campaigns!!!", weights.len(), campaigns.len());
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let total_weight = weights.iter().map(|weight| *weight as u128).sum::<u128>();
+    if total_weight == 0 {
+        msg!("The weights add up to zero!!!");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let offered = **donator_program_account.lamports.borrow();
+    if offered == 0 {
+        msg!("Nothing to donate");
+        return Err(CrowdfundingError::NothingToDonate.into());
+    }
+    /*
+     * computed on u128, no share is above "offered" so the casts can't truncate.
+     */
+    let mut shares: Vec<u64> = weights
+    .iter()
+    .map(|weight| (offered as u128 * *weight as u128 / total_weight) as u64)
+    .collect();
+    shares[0] += offered - shares.iter().sum::<u64>();
+
+    let now = current_clock(accounts)?.unix_timestamp;
+    let mut accepted = Vec::with_capacity(shares.len());
+    for ((writing_account, campaign_data, donor_tally), share) in campaigns.iter_mut().zip(&shares) {
+        let donated = accept_donation(campaign_data, writing_account, donator.key, *share, now)?;
+        if let Some(donor_tally) = donor_tally {
+            donor_tally.add(campaign_data, donated)?;
+        }
+        record_donation(campaign_data, writing_account.key, None, donated, donated, now)?;
+        accepted.push(donated);
+    }
+
+    /*
+     * every campaign and tally is written first, then the lamports move.
+     */
+    for ((writing_account, campaign_data, donor_tally), donated) in campaigns.iter().zip(&accepted) {
+        write_campaign(writing_account, campaign_data)?;
+        if let Some(donor_tally) = donor_tally {
+            donor_tally.write(program_id, writing_account.key, donator)?;
+        }
+
+        msg!(
+            "DONATION amount={} matched=0 total={} campaign={}",
+            donated,
+            campaign_data.amount_donated,
+            writing_account.key
+        );
+    }
+    for ((writing_account, ..), donated) in campaigns.iter().zip(&accepted) {
+        move_lamports(donator_program_account, writing_account, *donated)?;
+    }
+
+    Ok(())
+}
+/**********************************************************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::{
+        clock::Epoch, entrypoint::SUCCESS, instruction::Instruction, program::get_return_data, program_stubs,
+    };
+    use std::{cell::RefCell, sync::Once};
+
+    /*
+     * unix timestamp the Clock sysvar returns in every test.
+     */
+    const NOW: i64 = 1_650_000_000;
+
+    thread_local! {
+        /*
+         * Last "set_return_data" of the test running on this thread.
+         */
+        static RETURN_DATA: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+    }
+
+    /*
+     * Outside of the BPF runtime "Clock::get()" and "Rent::get()" go through these stubs.
+     */
+    struct TestSyscallStubs;
+    impl program_stubs::SyscallStubs for TestSyscallStubs {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Clock) = Clock {
+                    unix_timestamp: NOW,
+                    ..Clock::default()
+                };
+            }
+            SUCCESS
+        }
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Rent) = Rent::default();
+            }
+            SUCCESS
+        }
+        /*
+         * System program transfers move the lamports, every other CPI does nothing.
+         */
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            let is_transfer = instruction.data.get(..4) == Some(&[2, 0, 0, 0][..]);
+            if system_program::check_id(&instruction.program_id) && is_transfer {
+                let lamports = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+                let account = |key: &Pubkey| account_infos.iter().find(|info| info.key == key).unwrap();
+                **account(&instruction.accounts[0].pubkey).lamports.borrow_mut() -= lamports;
+                **account(&instruction.accounts[1].pubkey).lamports.borrow_mut() += lamports;
+            }
+            Ok(())
+        }
+        fn sol_set_return_data(&self, data: &[u8]) {
+            RETURN_DATA.with(|return_data| *return_data.borrow_mut() = data.to_vec());
+        }
+        fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+            let data = RETURN_DATA.with(|return_data| return_data.borrow().clone());
+            Some((Pubkey::default(), data))
+        }
+    }
+
+    fn setup() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs));
+        });
+    }
+
+    /*
+     * Owns everything an AccountInfo borrows, so a test can build the account list it needs.
+     */
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        is_signer: bool,
+    }
+
+    impl TestAccount {
+        fn new(owner: &Pubkey, lamports: u64, data: Vec<u8>) -> Self {
+            TestAccount {
+                key: Pubkey::new_unique(),
+                owner: *owner,
+                lamports,
+                data,
+                is_signer: false,
+            }
+        }
+        fn signer(lamports: u64) -> Self {
+            TestAccount {
+                is_signer: true,
+                ..TestAccount::new(&Pubkey::default(), lamports, vec![])
+            }
+        }
+        fn info(&mut self) -> AccountInfo {
+            AccountInfo::new(
+                &self.key,
+                self.is_signer,
+                true,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                Epoch::default(),
+            )
+        }
+    }
+
+    fn platform_account() -> TestAccount {
+        TestAccount {
+            key: platform::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        }
+    }
+
+    /*
+     * Empty AdminRegistry of "admin", as the program would create it.
+     */
+    fn admin_registry(program_id: &Pubkey, admin: &Pubkey) -> TestAccount {
+        let (registry_address, _) = admin_registry_pda(program_id, admin);
+        TestAccount {
+            key: registry_address,
+            ..TestAccount::new(program_id, 1_000_000, vec![0; 8])
+        }
+    }
+
+    fn campaign(admin: Pubkey) -> CampaignDetails {
+        CampaignDetails {
+            version: CAMPAIGN_VERSION,
+            admin,
+            name: String::from("campaign"),
+            description: String::from("description"),
+            image_link: String::from("https://example.com/image.png"),
+            amount_donated: 0,
+            target_amount: 1_000_000,
+            deadline: NOW + 3600,
+            donor_count: 0,
+            min_donation: 0,
+            status: CampaignStatus::Active as u8,
+            category: CampaignCategory::Other as u8,
+            beneficiary: admin,
+            campaign_type: CampaignType::Flexible as u8,
+            last_donor: Pubkey::default(),
+            mint: Pubkey::default(),
+            token_amount_donated: 0,
+            matcher: Pubkey::default(),
+            match_pool_remaining: 0,
+            max_withdraw_per_day: 0,
+            withdrawn_today: 0,
+            last_withdraw_ts: 0,
+            created_at: 0,
+            largest_donation: 0,
+            largest_donor: Pubkey::default(),
+            pool_fee_bps: 0,
+            next_withdraw_ts: 0,
+            interval_seconds: 0,
+            per_interval_amount: 0,
+            metadata_account: Pubkey::default(),
+            refunds_enabled: false,
+            required_signers: 0,
+            signers: Vec::new(),
+            cap_at_goal: false,
+            block_self_donation: false,
+            pending_withdraw_amount: 0,
+            withdraw_unlock_ts: 0,
+            max_donation_per_donor: 0,
+            goal_reached_at: 0,
+            rent_reserved: 0,
+            timelock_threshold: 0,
+            seed_admin: Pubkey::default(),
+            seed_name: String::new(),
+            bump: 0,
+        }
+    }
+
+    /*
+     * Run donate with a donator_program_account holding "donated" lamports and a matcher_program_account
+     * holding "match_reserve" lamports, campaign_data.matcher is moved to the matcher_pda of the writing_account.
+     * Returns the result, the campaign stored afterwards and the writing_account lamports.
+     */
+    fn run_donate(
+        program_id: &Pubkey,
+        campaign_data: &CampaignDetails,
+        donated: u64,
+        match_reserve: u64,
+    ) -> (ProgramResult, CampaignDetails, u64) {
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(program_id, 5_000_000, vec![]);
+        let mut campaign_data = campaign_data.clone();
+        if campaign_data.matcher != Pubkey::default() {
+            campaign_data.matcher = matcher_pda(program_id, &writing.key).0;
+        }
+        writing.data = campaign_data.try_to_vec().unwrap();
+        let mut donator_program = TestAccount::new(program_id, donated, vec![]);
+        let mut matcher_program = TestAccount {
+            key: campaign_data.matcher,
+            ..TestAccount::new(program_id, match_reserve, vec![])
+        };
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), matcher_program.info()];
+        let result = donate(program_id, &accounts, DonateRequest { donor, anonymous: false });
+        drop(accounts);
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        (result, stored, writing.lamports)
+    }
+
+    /*
+     * Run withdraw on an account holding "above_rent" lamports more than its rent_exemption and RENT_BUFFER_LAMPORTS,
+     * returns the result and the lamports received by the beneficiary.
+     */
+    fn run_withdraw(
+        program_id: &Pubkey,
+        campaign_data: &CampaignDetails,
+        above_rent: u64,
+        amount: u64,
+    ) -> (ProgramResult, u64) {
+        let mut admin = TestAccount {
+            key: campaign_data.admin,
+            ..TestAccount::signer(0)
+        };
+        let mut beneficiary = TestAccount {
+            key: campaign_data.beneficiary,
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+        let data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(data.len());
+        let mut writing = TestAccount::new(program_id, rent_exemption + RENT_BUFFER_LAMPORTS + above_rent, data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        let result = withdraw(program_id, &accounts, WithdrawRequest { amount });
+        drop(accounts);
+        (result, beneficiary.lamports)
+    }
+
+    #[test]
+    fn donate_reads_the_clock_account_when_passed() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.deadline = NOW + 100;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+        let mut clock = TestAccount {
+            key: sysvar::clock::id(),
+            ..TestAccount::new(&sysvar::id(), 0, vec![0; Clock::size_of()])
+        };
+        {
+            let mut clock_info = clock.info();
+            let passed_deadline = Clock { unix_timestamp: NOW + 200, ..Clock::default() };
+            passed_deadline.to_account_info(&mut clock_info).unwrap();
+        }
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), clock.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::CampaignExpired.into())
+        );
+        /*
+         * without the account the syscall clock (NOW) is used and the campaign is still open.
+         */
+        assert_eq!(donate(&program_id, &accounts[..3], DonateRequest { donor, anonymous: false }), Ok(()));
+        drop(accounts);
+    }
+
+    #[test]
+    fn goal_reached_at_comes_from_the_clock_account_when_passed() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.target_amount = 1_000;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+        let mut clock = TestAccount {
+            key: sysvar::clock::id(),
+            ..TestAccount::new(&sysvar::id(), 0, vec![0; Clock::size_of()])
+        };
+        {
+            let mut clock_info = clock.info();
+            let clock_time = Clock { unix_timestamp: NOW + 60, ..Clock::default() };
+            clock_time.to_account_info(&mut clock_info).unwrap();
+        }
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), clock.info()];
+        assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        drop(accounts);
+        assert_eq!(CampaignDetails::try_from_slice(&writing.data).unwrap().goal_reached_at, NOW + 60);
+    }
+
+    #[test]
+    fn sweep_wrong_mint_only_moves_foreign_tokens() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.mint = Pubkey::new_unique();
+        let mut writing = TestAccount {
+            key: campaign_pda(&program_id, &admin.key, &campaign_data.name).0,
+            ..TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap())
+        };
+        let token_account = |mint: Pubkey, owner: Pubkey| {
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            let tokens = spl_token::state::Account {
+                mint,
+                owner,
+                amount: 500,
+                state: spl_token::state::AccountState::Initialized,
+                ..spl_token::state::Account::default()
+            };
+            spl_token::state::Account::pack(tokens, &mut data).unwrap();
+            TestAccount::new(&spl_token::id(), 0, data)
+        };
+        let mut foreign = token_account(Pubkey::new_unique(), writing.key);
+        let mut donations = token_account(campaign_data.mint, writing.key);
+        let mut recovery = token_account(Pubkey::new_unique(), admin.key);
+        let mut token_program = TestAccount {
+            key: spl_token::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [
+            writing.info(),
+            admin.info(),
+            foreign.info(),
+            recovery.info(),
+            token_program.info(),
+            donations.info(),
+        ];
+        let donation_accounts =
+            [accounts[0].clone(), accounts[1].clone(), accounts[5].clone(), accounts[3].clone(), accounts[4].clone()];
+        assert_eq!(sweep_wrong_mint(&program_id, &donation_accounts), Err(ProgramError::InvalidArgument));
+        assert_eq!(sweep_wrong_mint(&program_id, &accounts[..5]), Ok(()));
+        drop((accounts, donation_accounts));
+    }
+
+    #[test]
+    fn sweep_wrong_mint_signs_with_the_creation_seeds() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(creator);
+        let (campaign_address, bump) = campaign_pda(&program_id, &creator, &campaign_data.name);
+        campaign_data.seed_admin = creator;
+        campaign_data.seed_name = campaign_data.name.clone();
+        campaign_data.bump = bump;
+        /*
+         * the admin changed with TransferAdmin and the campaign got renamed since its creation.
+         */
+        campaign_data.admin = admin.key;
+        campaign_data.name = String::from("renamed");
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap())
+        };
+        let mut tokens = vec![0; spl_token::state::Account::LEN];
+        let foreign_tokens = spl_token::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: campaign_address,
+            amount: 500,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        };
+        spl_token::state::Account::pack(foreign_tokens, &mut tokens).unwrap();
+        let mut foreign = TestAccount::new(&spl_token::id(), 0, tokens);
+        let mut recovery = TestAccount::new(&spl_token::id(), 0, vec![]);
+        let mut token_program = TestAccount {
+            key: spl_token::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [writing.info(), admin.info(), foreign.info(), recovery.info(), token_program.info()];
+        assert_eq!(sweep_wrong_mint(&program_id, &accounts), Ok(()));
+
+        /*
+         * without stored seeds the current admin and name no longer derive the campaign address.
+         */
+        campaign_data.seed_admin = Pubkey::default();
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(sweep_wrong_mint(&program_id, &accounts), Err(ProgramError::InvalidSeeds));
+        drop(accounts);
+    }
+
+    #[test]
+    fn withdraw_spl_pays_the_beneficiary_token_account_with_the_withdraw_checks() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        let (campaign_address, bump) = campaign_pda(&program_id, &admin.key, &campaign_data.name);
+        campaign_data.mint = Pubkey::new_unique();
+        campaign_data.beneficiary = Pubkey::new_unique();
+        campaign_data.seed_admin = admin.key;
+        campaign_data.seed_name = campaign_data.name.clone();
+        campaign_data.bump = bump;
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap())
+        };
+        let token_account = |mint: Pubkey, owner: Pubkey| {
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            let tokens = spl_token::state::Account {
+                mint,
+                owner,
+                amount: 500,
+                state: spl_token::state::AccountState::Initialized,
+                ..spl_token::state::Account::default()
+            };
+            spl_token::state::Account::pack(tokens, &mut data).unwrap();
+            TestAccount::new(&spl_token::id(), 0, data)
+        };
+        let mut donations = token_account(campaign_data.mint, writing.key);
+        let mut beneficiary_tokens = token_account(campaign_data.mint, campaign_data.beneficiary);
+        let mut admin_tokens = token_account(campaign_data.mint, admin.key);
+        let mut token_program = TestAccount {
+            key: spl_token::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [
+            writing.info(),
+            admin.info(),
+            donations.info(),
+            beneficiary_tokens.info(),
+            token_program.info(),
+            admin_tokens.info(),
+        ];
+        let to_admin =
+            [accounts[0].clone(), accounts[1].clone(), accounts[2].clone(), accounts[5].clone(), accounts[4].clone()];
+        assert_eq!(
+            withdraw_spl(&program_id, &to_admin, WithdrawRequest { amount: 500 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            withdraw_spl(&program_id, &accounts[..5], WithdrawRequest { amount: 501 }),
+            Err(ProgramError::InsufficientFunds)
+        );
+
+        campaign_data.status = CampaignStatus::Flagged as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(
+            withdraw_spl(&program_id, &accounts[..5], WithdrawRequest { amount: 500 }),
+            Err(CrowdfundingError::CampaignFlagged.into())
+        );
+
+        campaign_data.status = CampaignStatus::Active as u8;
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(
+            withdraw_spl(&program_id, &accounts[..5], WithdrawRequest { amount: 500 }),
+            Err(CrowdfundingError::CampaignStillActive.into())
+        );
+
+        campaign_data.campaign_type = CampaignType::Flexible as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(withdraw_spl(&program_id, &accounts[..5], WithdrawRequest { amount: 500 }), Ok(()));
+        drop((accounts, to_admin));
+    }
+
+    #[test]
+    fn donate_spl_applies_the_donation_checks() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.mint = Pubkey::new_unique();
+        campaign_data.min_donation = 100;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let token_account = |mint: Pubkey, owner: Pubkey| {
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            let tokens = spl_token::state::Account {
+                mint,
+                owner,
+                amount: 500,
+                state: spl_token::state::AccountState::Initialized,
+                ..spl_token::state::Account::default()
+            };
+            spl_token::state::Account::pack(tokens, &mut data).unwrap();
+            TestAccount::new(&spl_token::id(), 0, data)
+        };
+        let mut donator_tokens = token_account(campaign_data.mint, donator.key);
+        let mut campaign_tokens = token_account(campaign_data.mint, writing.key);
+        let mut token_program = TestAccount {
+            key: spl_token::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts =
+            [writing.info(), donator_tokens.info(), campaign_tokens.info(), donator.info(), token_program.info()];
+        let request = |amount| DonateSplRequest { amount, anonymous: true };
+        assert_eq!(donate_spl(&program_id, &accounts, request(0)), Err(CrowdfundingError::NothingToDonate.into()));
+        assert_eq!(donate_spl(&program_id, &accounts, request(99)), Err(CrowdfundingError::DonationTooSmall.into()));
+
+        campaign_data.status = CampaignStatus::Paused as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(donate_spl(&program_id, &accounts, request(100)), Err(CrowdfundingError::CampaignPaused.into()));
+
+        campaign_data.status = CampaignStatus::Active as u8;
+        campaign_data.admin = donor;
+        campaign_data.block_self_donation = true;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(donate_spl(&program_id, &accounts, request(100)), Err(CrowdfundingError::SelfDonation.into()));
+
+        campaign_data.block_self_donation = false;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(donate_spl(&program_id, &accounts, request(100)), Ok(()));
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.token_amount_donated, 100);
+        assert_eq!(stored.donor_count, 1);
+        assert_eq!(stored.last_donor, Pubkey::default());
+    }
+
+    #[test]
+    fn donate_rejects_amount_donated_overflow() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.amount_donated = u64::MAX - 10;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(donator_program.lamports, 100);
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, u64::MAX - 10);
+    }
+
+    #[test]
+    fn donate_moves_no_lamports_when_writing_campaign_fails() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        /*
+         * an outstanding borrow of the campaign data makes write_campaign fail.
+         */
+        let data_borrow = accounts[0].data.borrow();
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(ProgramError::AccountBorrowFailed)
+        );
+        drop(data_borrow);
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(donator_program.lamports, 100);
+    }
+
+    #[test]
+    fn donate_reports_accounts_in_the_wrong_position() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+        let mut other_program = TestAccount::new(&Pubkey::new_unique(), 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), other_program.info()];
+        let request = || DonateRequest { donor, anonymous: false };
+        /*
+         * the donator first: position 2 is not a signer.
+         */
+        let donator_first = [accounts[2].clone(), accounts[1].clone(), accounts[0].clone()];
+        assert_eq!(donate(&program_id, &donator_first, request()), Err(CrowdfundingError::NotSigner.into()));
+        /*
+         * the campaign and the donator_program_account swapped: position 0 holds no campaign.
+         */
+        let programs_swapped = [accounts[1].clone(), accounts[0].clone(), accounts[2].clone()];
+        assert_eq!(donate(&program_id, &programs_swapped, request()), Err(ProgramError::UninitializedAccount));
+        /*
+         * an account of another program in position 1.
+         */
+        let foreign_second = [accounts[0].clone(), accounts[3].clone(), accounts[2].clone()];
+        assert_eq!(donate(&program_id, &foreign_second, request()), Err(ProgramError::IncorrectProgramId));
+        drop((accounts, donator_first, programs_swapped, foreign_second));
+
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(donator_program.lamports, 100);
+    }
+
+    #[test]
+    fn donate_can_not_drain_a_program_account_holding_data() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let mut victim = TestAccount::new(&program_id, 7_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+
+        let accounts = [writing.info(), victim.info(), donator.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(ProgramError::InvalidAccountData)
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(victim.lamports, 7_000_000);
+    }
+
+    #[test]
+    fn fund_match_pool_only_takes_an_account_without_data() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut sponsor = TestAccount::signer(0);
+        let mut writing = TestAccount::new(&program_id, 5_000_000, vec![]);
+        let mut matcher = TestAccount {
+            key: matcher_pda(&program_id, &writing.key).0,
+            ..TestAccount::new(&program_id, 0, vec![])
+        };
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.matcher = matcher.key;
+        writing.data = campaign_data.try_to_vec().unwrap();
+        let mut victim = TestAccount::new(&program_id, 7_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let mut sponsor_program = TestAccount::new(&program_id, 1_000, vec![]);
+
+        let accounts = [writing.info(), victim.info(), matcher.info(), sponsor.info(), sponsor_program.info()];
+        assert_eq!(fund_match_pool(&program_id, &accounts), Err(ProgramError::InvalidAccountData));
+        let funded = [accounts[0].clone(), accounts[4].clone(), accounts[2].clone(), accounts[3].clone()];
+        campaign_data.status = CampaignStatus::Closed as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(fund_match_pool(&program_id, &funded), Err(CrowdfundingError::CampaignClosed.into()));
+        campaign_data.status = CampaignStatus::Flagged as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(fund_match_pool(&program_id, &funded), Err(CrowdfundingError::CampaignFlagged.into()));
+        campaign_data.status = CampaignStatus::Active as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(fund_match_pool(&program_id, &funded), Ok(()));
+        drop((accounts, funded));
+
+        assert_eq!(victim.lamports, 7_000_000);
+        assert_eq!(matcher.lamports, 1_000);
+        assert_eq!(CampaignDetails::try_from_slice(&writing.data).unwrap().match_pool_remaining, 1_000);
+    }
+
+    #[test]
+    fn donate_without_donor_signature_is_rejected_first() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let donor = donator.key;
+        /*
+         * the writing_account is not even owned by the program, the missing signature is reported first.
+         */
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&Pubkey::new_unique(), 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::NotSigner.into())
+        );
+        drop(accounts);
+        assert_eq!(donator_program.lamports, 100);
+    }
+
+    #[test]
+    fn donate_to_a_zeroed_account_is_rejected() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, vec![0; campaign_account_size()]);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(ProgramError::UninitializedAccount)
+        );
+        drop(accounts);
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(donator_program.lamports, 100);
+    }
+
+    #[test]
+    fn donating_the_same_account_twice_is_rejected() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::NothingToDonate.into())
+        );
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, 100);
+        assert_eq!(stored.donor_count, 1);
+        assert_eq!(writing.lamports, 5_000_100);
+    }
+
+    #[test]
+    fn donate_direct_transfers_from_the_donator_wallet() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(1_000);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 1, vec![])
+        };
+
+        let accounts = [writing.info(), donator.info(), system.info()];
+        assert_eq!(
+            donate_direct(&program_id, &accounts, DonateDirectRequest { amount: 0, anonymous: false }),
+            Err(CrowdfundingError::NothingToDonate.into())
+        );
+        assert_eq!(
+            donate_direct(&program_id, &accounts, DonateDirectRequest { amount: 400, anonymous: false }),
+            Ok(())
+        );
+        drop(accounts);
+
+        assert_eq!(donator.lamports, 600);
+        assert_eq!(writing.lamports, 5_000_400);
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, 400);
+        assert_eq!(stored.donor_count, 1);
+        assert_eq!(stored.last_donor, donor);
+    }
+
+    #[test]
+    fn donate_direct_applies_the_cap_and_the_donor_limit() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(1_000);
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 1, vec![])
+        };
+        let mut capped_data = campaign(Pubkey::new_unique());
+        capped_data.target_amount = 1_000;
+        capped_data.amount_donated = 800;
+        capped_data.cap_at_goal = true;
+        let mut capped = TestAccount::new(&program_id, 5_000_000, capped_data.try_to_vec().unwrap());
+        let mut limited_data = campaign(Pubkey::new_unique());
+        limited_data.max_donation_per_donor = 100;
+        let mut limited = TestAccount::new(&program_id, 5_000_000, limited_data.try_to_vec().unwrap());
+        let mut tally = TestAccount {
+            key: donor_tally_pda(&program_id, &limited.key, &donator.key).0,
+            ..TestAccount::new(&program_id, 1_000_000, vec![0; 8])
+        };
+
+        let accounts = [capped.info(), donator.info(), system.info()];
+        assert_eq!(
+            donate_direct(&program_id, &accounts, DonateDirectRequest { amount: 500, anonymous: false }),
+            Ok(())
+        );
+        drop(accounts);
+        let accounts = [limited.info(), donator.info(), system.info(), tally.info()];
+        assert_eq!(
+            donate_direct(&program_id, &accounts, DonateDirectRequest { amount: 101, anonymous: false }),
+            Err(CrowdfundingError::DonorLimitExceeded.into())
+        );
+        drop(accounts);
+
+        /*
+         * only the 200 lamports left to the goal left the donator wallet.
+         */
+        assert_eq!(donator.lamports, 800);
+        assert_eq!(capped.lamports, 5_000_200);
+        let stored = CampaignDetails::try_from_slice(&capped.data).unwrap();
+        assert_eq!(stored.amount_donated, 1_000);
+        assert_eq!(stored.goal_reached_at, NOW);
+        assert_eq!(limited.lamports, 5_000_000);
+    }
+
+    #[test]
+    fn donate_to_a_campaign_below_rent_exemption_is_rejected() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption - 1, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        );
+        drop(accounts);
+        assert_eq!(writing.lamports, rent_exemption - 1);
+        assert_eq!(donator_program.lamports, 100);
+    }
+
+    #[test]
+    fn capped_campaign_fills_exactly_to_the_goal() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.cap_at_goal = true;
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 400;
+
+        let (result, stored, lamports) = run_donate(&program_id, &campaign_data, 600, 0);
+        assert_eq!(result, Ok(()));
+        assert_eq!(stored.amount_donated, 1_000);
+        assert_eq!(lamports, 5_000_600);
+        assert!(!is_overfunded(&stored));
+
+        let (result, stored, lamports) = run_donate(&program_id, &stored, 1, 0);
+        assert_eq!(result, Err(CrowdfundingError::GoalReached.into()));
+        assert_eq!(stored.amount_donated, 1_000);
+        assert_eq!(lamports, 5_000_000);
+    }
+
+    #[test]
+    fn capped_campaign_leaves_the_overfill_with_the_donator() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.cap_at_goal = true;
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 400;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, 1_000);
+        assert_eq!(stored.largest_donation, 600);
+        assert_eq!(writing.lamports, 5_000_600);
+        assert_eq!(donator_program.lamports, 400);
+    }
+
+    #[test]
+    fn donate_with_receipt_records_the_donation() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let campaign_address = writing.key;
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+        let (receipt_address, _) = receipt_pda(&program_id, &donor, &campaign_address, NOW);
+        let receipt_len = DonationReceipt::default().try_to_vec().unwrap().len();
+        let mut receipt = TestAccount {
+            key: receipt_address,
+            ..TestAccount::new(&program_id, 1_000_000, vec![0; receipt_len])
+        };
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), receipt.info(), system.info()];
+        assert_eq!(
+            donate_with_receipt(&program_id, &accounts, DonateRequest { donor, anonymous: false }, String::new()),
+            Ok(())
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_001_000);
+        assert_eq!(
+            DonationReceipt::try_from_slice(&receipt.data).unwrap(),
+            DonationReceipt { campaign: campaign_address, donor, amount: 1_000, timestamp: NOW, message: String::new() }
+        );
+    }
+
+    #[test]
+    fn donate_with_receipt_takes_over_a_pre_funded_receipt() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let receipt_len = DonationReceipt::default().try_to_vec().unwrap().len();
+        let rent_exemption = Rent::default().minimum_balance(receipt_len);
+        let mut donator = TestAccount::signer(rent_exemption);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let campaign_address = writing.key;
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+        /*
+         * someone sent a lamport to the receipt address before the donation.
+         */
+        let mut receipt = TestAccount {
+            key: receipt_pda(&program_id, &donor, &campaign_address, NOW).0,
+            ..TestAccount::new(&Pubkey::default(), 1, vec![0; receipt_len])
+        };
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), receipt.info(), system.info()];
+        assert_eq!(
+            donate_with_receipt(&program_id, &accounts, DonateRequest { donor, anonymous: false }, String::new()),
+            Ok(())
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_001_000);
+        assert_eq!(receipt.lamports, rent_exemption);
+        assert_eq!(donator.lamports, 1);
+        assert_eq!(DonationReceipt::try_from_slice(&receipt.data).unwrap().amount, 1_000);
+    }
+
+    #[test]
+    fn admin_donation_is_refused_when_self_donation_is_blocked() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let donor = admin.key;
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.block_self_donation = true;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), admin.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::SelfDonation.into())
+        );
+        let mut campaign_data = load_campaign(&accounts[0]).unwrap();
+        campaign_data.block_self_donation = false;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_001_000);
+    }
+
+    #[test]
+    fn donations_of_a_donor_are_capped_in_total() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.max_donation_per_donor = 1_000;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+        let (tally_address, _) = donor_tally_pda(&program_id, &writing.key, &donor);
+        let mut tally = TestAccount {
+            key: tally_address,
+            ..TestAccount::new(&program_id, 1_000_000, vec![0; 8])
+        };
+
+        for (donated, expected) in [
+            (600, Ok(())),
+            (400, Ok(())),
+            (1, Err(ProgramError::from(CrowdfundingError::DonorLimitExceeded))),
+        ] {
+            let mut donator_program = TestAccount::new(&program_id, donated, vec![]);
+            let accounts = [writing.info(), donator_program.info(), donator.info(), tally.info()];
+            assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }), expected);
+        }
+
+        assert_eq!(DonorTally::try_from_slice(&tally.data).unwrap().total_donated, 1_000);
+        assert_eq!(writing.lamports, 5_001_000);
+    }
+
+    #[test]
+    fn donate_rejects_the_writing_account_as_donator_program_account() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+
+        let writing_info = writing.info();
+        let accounts = [writing_info.clone(), writing_info, donator.info()];
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }),
+            Err(ProgramError::InvalidArgument)
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(CampaignDetails::try_from_slice(&writing.data).unwrap().donor_count, 0);
+    }
+
+    #[test]
+    fn donate_with_message_keeps_the_message_in_the_receipt() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let (receipt_address, _) = receipt_pda(&program_id, &donor, &writing.key, NOW);
+        let message = String::from("Good luck with the build!");
+        let receipt_len = DonationReceipt::default().try_to_vec().unwrap().len() + message.len();
+        let mut receipt = TestAccount {
+            key: receipt_address,
+            ..TestAccount::new(&program_id, 1_000_000, vec![0; receipt_len])
+        };
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        for (message, expected) in [
+            ("m".repeat(MAX_MESSAGE_LEN + 1), Err(ProgramError::InvalidInstructionData)),
+            (message.clone(), Ok(())),
+        ] {
+            let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+            let accounts = [writing.info(), donator_program.info(), donator.info(), receipt.info(), system.info()];
+            let input_data = DonateWithMessageRequest { donor, anonymous: false, message };
+            assert_eq!(donate_with_message(&program_id, &accounts, input_data), expected);
+        }
+
+        let stored = DonationReceipt::try_from_slice(&receipt.data).unwrap();
+        assert_eq!(stored.message, message);
+        assert_eq!(stored.amount, 1_000);
+    }
+
+    #[test]
+    fn goal_reached_at_is_set_once() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 400;
+
+        let (result, stored, _) = run_donate(&program_id, &campaign_data, 500, 0);
+        assert_eq!(result, Ok(()));
+        assert_eq!(stored.goal_reached_at, 0);
+
+        let (result, mut stored, _) = run_donate(&program_id, &stored, 100, 0);
+        assert_eq!(result, Ok(()));
+        assert_eq!(stored.goal_reached_at, NOW);
+
+        stored.goal_reached_at = NOW - 3600;
+        let (result, stored, _) = run_donate(&program_id, &stored, 100, 0);
+        assert_eq!(result, Ok(()));
+        assert_eq!(stored.goal_reached_at, NOW - 3600);
+    }
+
+    #[test]
+    fn anonymous_donation_does_not_record_the_donor() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), donator_program.info(), donator.info()];
+        assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: true }), Ok(()));
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, 100);
+        assert_eq!(stored.donor_count, 1);
+        assert_eq!(stored.last_donor, Pubkey::default());
+    }
+
+    #[test]
+    fn donate_request_without_the_flag_is_anonymous() {
+        let donor = Pubkey::new_unique();
+        let mut instruction_data = vec![2];
+        instruction_data.extend(donor.to_bytes());
+        match CrowdfundingInstruction::try_from_slice(&instruction_data).unwrap() {
+            CrowdfundingInstruction::Donate(request) => {
+                assert_eq!(request.donor, donor);
+                assert!(request.anonymous);
+            }
+            instruction => panic!("unexpected instruction {:?}", instruction),
+        }
+
+        instruction_data.push(0);
+        match CrowdfundingInstruction::try_from_slice(&instruction_data).unwrap() {
+            CrowdfundingInstruction::Donate(request) => assert!(!request.anonymous),
+            instruction => panic!("unexpected instruction {:?}", instruction),
+        }
+    }
+
+    #[test]
+    fn larger_donation_replaces_the_largest_donor() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut first = TestAccount::signer(0);
+        let mut second = TestAccount::signer(0);
+        let (first_donor, second_donor) = (first.key, second.key);
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut first_program = TestAccount::new(&program_id, 100, vec![]);
+        let mut second_program = TestAccount::new(&program_id, 300, vec![]);
+        let mut smaller_program = TestAccount::new(&program_id, 200, vec![]);
+
+        let accounts = [
+            writing.info(),
+            first_program.info(),
+            second_program.info(),
+            smaller_program.info(),
+            first.info(),
+            second.info(),
+        ];
+        let donate_from = |donator_program: usize, donator: usize, donor: Pubkey| {
+            let donate_accounts = [accounts[0].clone(), accounts[donator_program].clone(), accounts[donator].clone()];
+            donate(&program_id, &donate_accounts, DonateRequest { donor, anonymous: false })
+        };
+
+        assert_eq!(donate_from(1, 4, first_donor), Ok(()));
+        let stored = load_campaign(&accounts[0]).unwrap();
+        assert_eq!((stored.largest_donation, stored.largest_donor), (100, first_donor));
+
+        assert_eq!(donate_from(2, 5, second_donor), Ok(()));
+        let stored = load_campaign(&accounts[0]).unwrap();
+        assert_eq!((stored.largest_donation, stored.largest_donor), (300, second_donor));
+
+        assert_eq!(donate_from(3, 4, first_donor), Ok(()));
+        let stored = load_campaign(&accounts[0]).unwrap();
+        assert_eq!((stored.largest_donation, stored.largest_donor), (300, second_donor));
+    }
+
+    #[test]
+    fn donate_is_refused_while_paused() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), admin.info(), donator_program.info(), donator.info()];
+        let admin_accounts = [accounts[0].clone(), accounts[1].clone()];
+        let donate_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[3].clone()];
+
+        assert_eq!(set_paused(&program_id, &admin_accounts, true), Ok(()));
+        assert_eq!(
+            donate(&program_id, &donate_accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::CampaignPaused.into())
+        );
+        assert_eq!(set_paused(&program_id, &admin_accounts, false), Ok(()));
+        assert_eq!(donate(&program_id, &donate_accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        assert_eq!(accounts[0].lamports(), 5_000_100);
+    }
+
+    #[test]
+    fn donate_is_refused_while_flagged() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut platform = TestAccount {
+            is_signer: true,
+            ..platform_account()
+        };
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let campaign_data = campaign(Pubkey::new_unique()).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+
+        let accounts = [writing.info(), platform.info(), donator_program.info(), donator.info()];
+        let platform_accounts = [accounts[0].clone(), accounts[1].clone()];
+        let donate_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[3].clone()];
+
+        assert_eq!(set_flagged(&program_id, &platform_accounts, true), Ok(()));
+        assert_eq!(
+            donate(&program_id, &donate_accounts, DonateRequest { donor, anonymous: false }),
+            Err(CrowdfundingError::CampaignFlagged.into())
+        );
+        assert_eq!(set_flagged(&program_id, &platform_accounts, false), Ok(()));
+        assert_eq!(donate(&program_id, &donate_accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        assert_eq!(accounts[0].lamports(), 5_000_100);
+    }
+
+    #[test]
+    fn donate_needs_an_active_campaign() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        for (status, expected) in [
+            (CampaignStatus::Active as u8, Ok(())),
+            (CampaignStatus::Paused as u8, Err(ProgramError::from(CrowdfundingError::CampaignPaused))),
+            (CampaignStatus::Closed as u8, Err(ProgramError::from(CrowdfundingError::CampaignClosed))),
+            (CampaignStatus::Flagged as u8, Err(ProgramError::from(CrowdfundingError::CampaignFlagged))),
+            (4, Err(ProgramError::InvalidAccountData)),
+        ] {
+            let mut campaign_data = campaign(Pubkey::new_unique());
+            campaign_data.status = status;
+            let (result, stored, lamports) = run_donate(&program_id, &campaign_data, 100, 0);
+            assert_eq!(result, expected, "{}", status);
+            assert_eq!(stored.donor_count, if expected.is_ok() { 1 } else { 0 });
+            assert_eq!(lamports, if expected.is_ok() { 5_000_100 } else { 5_000_000 });
+        }
+    }
+
+    #[test]
+    fn only_the_platform_can_flag() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+
+        let accounts = [writing.info(), admin.info()];
+        assert_eq!(
+            set_flagged(&program_id, &accounts, true),
+            Err(CrowdfundingError::WrongAdmin.into())
+        );
+        drop(accounts);
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.status, CampaignStatus::Active as u8);
+    }
+
+    #[test]
+    fn donate_split_gives_the_remainder_to_the_first_campaign() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+        let mut writings = [
+            TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap()),
+            TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap()),
+            TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap()),
+        ];
+
+        let [first, second, third] = &mut writings;
+        let accounts = [first.info(), donator_program.info(), donator.info(), second.info(), third.info()];
+        assert_eq!(donate_split(&program_id, &accounts, DonateSplitRequest { weights: vec![] }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(donator_program.lamports, 0);
+        for (writing, share) in writings.iter().zip([34, 33, 33]) {
+            assert_eq!(writing.lamports, 5_000_000 + share);
+            let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+            assert_eq!(stored.amount_donated, share);
+            assert_eq!(stored.donor_count, 1);
+        }
+    }
+
+    #[test]
+    fn donate_split_by_weights() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+        let mut first = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let mut second = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+
+        let accounts = [first.info(), donator_program.info(), donator.info(), second.info()];
+        assert_eq!(
+            donate_split(&program_id, &accounts, DonateSplitRequest { weights: vec![1] }),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            donate_split(&program_id, &accounts, DonateSplitRequest { weights: vec![0, 0] }),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(donate_split(&program_id, &accounts, DonateSplitRequest { weights: vec![3, 1] }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(first.lamports, 5_000_750);
+        assert_eq!(second.lamports, 5_000_250);
+    }
+
+    #[test]
+    fn donate_split_applies_the_donate_accounting() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+        let mut capped_data = campaign(Pubkey::new_unique());
+        capped_data.target_amount = 30;
+        capped_data.cap_at_goal = true;
+        let mut capped = TestAccount::new(&program_id, 5_000_000, capped_data.try_to_vec().unwrap());
+        let mut blocking_data = campaign(donator.key);
+        blocking_data.block_self_donation = true;
+        let mut blocking = TestAccount::new(&program_id, 5_000_000, blocking_data.try_to_vec().unwrap());
+        let mut other = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+
+        let accounts = [capped.info(), donator_program.info(), donator.info(), blocking.info(), other.info()];
+        let listed_twice = [accounts[0].clone(), accounts[1].clone(), accounts[2].clone(), accounts[0].clone()];
+        let self_donation = [accounts[0].clone(), accounts[1].clone(), accounts[2].clone(), accounts[3].clone()];
+        let split = [accounts[0].clone(), accounts[1].clone(), accounts[2].clone(), accounts[4].clone()];
+        let request = || DonateSplitRequest { weights: vec![] };
+        assert_eq!(donate_split(&program_id, &listed_twice, request()), Err(ProgramError::InvalidArgument));
+        assert_eq!(
+            donate_split(&program_id, &self_donation, request()),
+            Err(CrowdfundingError::SelfDonation.into())
+        );
+        assert_eq!(donate_split(&program_id, &split, request()), Ok(()));
+        drop((accounts, listed_twice, self_donation, split));
+
+        /*
+         * the capped campaign took 30 of its share of 50, the other 20 stay with the donor.
+         */
+        assert_eq!(donator_program.lamports, 20);
+        assert_eq!(capped.lamports, 5_000_030);
+        assert_eq!(other.lamports, 5_000_050);
+        let stored = CampaignDetails::try_from_slice(&capped.data).unwrap();
+        assert_eq!(stored.amount_donated, 30);
+        assert_eq!(stored.goal_reached_at, NOW);
+        assert_eq!(blocking.lamports, 5_000_000);
+    }
+
+    #[test]
+    fn donate_split_rejects_a_campaign_not_owned_by_the_program() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let mut donator_program = TestAccount::new(&program_id, 100, vec![]);
+        let mut first = TestAccount::new(&program_id, 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+        let mut foreign = TestAccount::new(&Pubkey::new_unique(), 5_000_000, campaign(Pubkey::new_unique()).try_to_vec().unwrap());
+
+        let accounts = [first.info(), donator_program.info(), donator.info(), foreign.info()];
+        assert_eq!(
+            donate_split(&program_id, &accounts, DonateSplitRequest { weights: vec![] }),
+            Err(ProgramError::IncorrectProgramId)
+        );
+        drop(accounts);
+        assert_eq!(donator_program.lamports, 100);
+        assert_eq!(first.lamports, 5_000_000);
+    }
+
+    #[test]
+    fn pool_fee_is_split_from_the_donation_into_the_match_pool() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.matcher = Pubkey::new_unique();
+        campaign_data.pool_fee_bps = 1_000;
+
+        let (result, stored, writing_lamports) = run_donate(&program_id, &campaign_data, 1_000, 0);
+        assert_eq!(result, Ok(()));
+        assert_eq!(writing_lamports, 5_000_900);
+        assert_eq!(stored.amount_donated, 900);
+        assert_eq!(stored.match_pool_remaining, 100);
+
+        /*
+         * the next donation is matched from the pool fed by the previous one.
+         */
+        campaign_data.match_pool_remaining = 100;
+        let (result, stored, writing_lamports) = run_donate(&program_id, &campaign_data, 1_000, 100);
+        assert_eq!(result, Ok(()));
+        assert_eq!(writing_lamports, 5_001_000);
+        assert_eq!(stored.amount_donated, 1_000);
+        assert_eq!(stored.match_pool_remaining, 100);
+    }
+
+    #[test]
+    fn pool_fee_donation_creates_the_match_pool_without_a_sponsor() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut donator = TestAccount::signer(0);
+        let donor = donator.key;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, vec![]);
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.matcher = matcher_pda(&program_id, &writing.key).0;
+        campaign_data.pool_fee_bps = 1_000;
+        writing.data = campaign_data.try_to_vec().unwrap();
+        let mut donator_program = TestAccount::new(&program_id, 1_000, vec![]);
+        let mut matcher = TestAccount {
+            key: campaign_data.matcher,
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [writing.info(), donator_program.info(), donator.info(), matcher.info(), system.info()];
+        assert_eq!(
+            donate(&program_id, &accounts[..4], DonateRequest { donor, anonymous: false }),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(donate(&program_id, &accounts, DonateRequest { donor, anonymous: false }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_000_900);
+        assert_eq!(matcher.lamports, 100);
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, 900);
+        assert_eq!(stored.match_pool_remaining, 100);
+    }
+
+    #[test]
+    fn donate_is_matched_while_the_pool_lasts() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.matcher = Pubkey::new_unique();
+
+        /*
+         * full match, partial match and exhausted pool.
+         */
+        for (pool, matched) in [(1_000, 400), (300, 300), (0, 0)] {
+            campaign_data.match_pool_remaining = pool;
+            let (result, stored, lamports) = run_donate(&program_id, &campaign_data, 400, 1_000);
+            assert_eq!(result, Ok(()));
+            assert_eq!(stored.amount_donated, 400 + matched);
+            assert_eq!(stored.match_pool_remaining, pool - matched);
+            assert_eq!(lamports, 5_000_000 + 400 + matched);
+        }
+    }
+
+    #[test]
+    fn withdraw_rejects_dipping_below_rent_exemption() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(
+            withdraw(&program_id, &accounts, WithdrawRequest { amount: 1 }),
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, rent_exemption);
+        assert_eq!(beneficiary.lamports, 0);
+    }
+
+    #[test]
+    fn withdraw_is_frozen_when_flagged_but_not_when_paused() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+
+        campaign_data.status = CampaignStatus::Paused as u8;
+        assert_eq!(run_withdraw(&program_id, &campaign_data, 1_000, 1_000), (Ok(()), 990));
+
+        campaign_data.status = CampaignStatus::Flagged as u8;
+        assert_eq!(
+            run_withdraw(&program_id, &campaign_data, 1_000, 1_000),
+            (Err(CrowdfundingError::CampaignFlagged.into()), 0)
+        );
+    }
+
+    #[test]
+    fn withdraw_keeps_the_rent_buffer() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let campaign_data = campaign(Pubkey::new_unique());
+        assert_eq!(run_withdraw(&program_id, &campaign_data, 1_000, 1_000), (Ok(()), 990));
+        assert_eq!(
+            run_withdraw(&program_id, &campaign_data, 999, 1_000),
+            (Err(CrowdfundingError::InsufficientFundsForRent.into()), 0)
+        );
+    }
+
+    #[test]
+    fn withdraw_all_leaves_the_rent_exemption_and_buffer() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(withdraw_all(&program_id, &accounts), Ok(()));
+        assert_eq!(withdraw_all(&program_id, &accounts), Err(ProgramError::InsufficientFunds));
+        drop(accounts);
+
+        assert_eq!(writing.lamports, rent_exemption + RENT_BUFFER_LAMPORTS);
+        assert_eq!(platform.lamports + beneficiary.lamports, 1_000_000);
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.withdrawn_today, 1_000_000);
+    }
+
+    #[test]
+    fn withdraw_split_pays_every_recipient() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut charity = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut overhead = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), charity.info(), overhead.info()];
+        assert_eq!(
+            withdraw_split(&program_id, &accounts, WithdrawSplitRequest { amounts: vec![900_000, 100_001] }),
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        );
+        assert_eq!(
+            withdraw_split(&program_id, &accounts, WithdrawSplitRequest { amounts: vec![900_000, 50_000, 50_000] }),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            withdraw_split(&program_id, &accounts, WithdrawSplitRequest { amounts: vec![900_000, 100_000] }),
+            Ok(())
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, rent_exemption + RENT_BUFFER_LAMPORTS);
+        assert_eq!(charity.lamports, 891_000);
+        assert_eq!(overhead.lamports, 99_000);
+        assert_eq!(platform.lamports, 10_000);
+    }
+
+    #[test]
+    fn can_withdraw_checks_without_moving_lamports() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let lamports = rent_exemption + RENT_BUFFER_LAMPORTS + 1_000;
+        let mut writing = TestAccount::new(&program_id, lamports, campaign_data.clone());
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(can_withdraw(&program_id, &accounts, WithdrawRequest { amount: 1_000 }), Ok(()));
+        assert_eq!(
+            can_withdraw(&program_id, &accounts, WithdrawRequest { amount: 1_001 }),
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        );
+        drop(accounts);
+
+        assert_eq!(writing.lamports, lamports);
+        assert_eq!(writing.data, campaign_data);
+        assert_eq!(beneficiary.lamports, 0);
+        assert_eq!(platform.lamports, 0);
+    }
+
+    #[test]
+    fn scheduled_withdrawal_runs_once_due() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 10_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        let admin_accounts = [accounts[0].clone(), accounts[1].clone()];
+        let execute_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[3].clone()];
+        let schedule = |next_withdraw_ts| ScheduleWithdrawalRequest {
+            next_withdraw_ts,
+            interval_seconds: 3600,
+            per_interval_amount: 1_000,
+        };
+
+        assert_eq!(schedule_withdrawal(&program_id, &admin_accounts, schedule(NOW + 1)), Ok(()));
+        assert_eq!(
+            execute_scheduled_withdrawal(&program_id, &execute_accounts),
+            Err(CrowdfundingError::ScheduledWithdrawalNotDue.into())
+        );
+        assert_eq!(accounts[3].lamports(), 0);
+
+        assert_eq!(schedule_withdrawal(&program_id, &admin_accounts, schedule(NOW)), Ok(()));
+        assert_eq!(execute_scheduled_withdrawal(&program_id, &execute_accounts), Ok(()));
+        assert_eq!(accounts[3].lamports(), 990);
+        assert_eq!(accounts[2].lamports(), 10);
+        assert_eq!(load_campaign(&accounts[0]).unwrap().next_withdraw_ts, NOW + 3600);
+        assert_eq!(
+            execute_scheduled_withdrawal(&program_id, &execute_accounts),
+            Err(CrowdfundingError::ScheduledWithdrawalNotDue.into())
+        );
+    }
+
+    #[test]
+    fn set_metadata_links_one_metadata_account() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data);
+        let mut metadata = TestAccount::new(&program_id, 5_000_000, vec![0; 1024]);
+        let mut other = TestAccount::new(&program_id, 5_000_000, vec![0; 1024]);
+
+        let accounts = [writing.info(), admin.info(), metadata.info(), other.info()];
+        let request = || SetMetadataRequest {
+            description: String::from("# A long *markdown* description"),
+            links: vec![String::from("https://example.com")],
+        };
+        assert_eq!(set_metadata(&program_id, &accounts[..3], request()), Ok(()));
+        assert_eq!(
+            set_metadata(&program_id, &[accounts[0].clone(), accounts[1].clone(), accounts[3].clone()], request()),
+            Err(ProgramError::InvalidArgument)
+        );
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.metadata_account, metadata.key);
+        let stored_metadata = CampaignMetadata::deserialize(&mut metadata.data.as_slice()).unwrap();
+        assert_eq!(stored_metadata.campaign, writing.key);
+        assert_eq!(stored_metadata.description, "# A long *markdown* description");
+        assert_eq!(stored_metadata.links, vec![String::from("https://example.com")]);
+    }
+
+    #[test]
+    fn refunds_need_a_failed_all_or_nothing_campaign() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.amount_donated = campaign_data.target_amount;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info()];
+        assert_eq!(
+            enable_refunds(&program_id, &accounts),
+            Err(CrowdfundingError::CampaignStillActive.into())
+        );
+        campaign_data.deadline = NOW - 1;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(enable_refunds(&program_id, &accounts), Err(CrowdfundingError::GoalReached.into()));
+
+        campaign_data.campaign_type = CampaignType::Flexible as u8;
+        campaign_data.amount_donated = 0;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(enable_refunds(&program_id, &accounts), Err(ProgramError::InvalidArgument));
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert!(!stored.refunds_enabled);
+    }
+
+    #[test]
+    fn enabled_refunds_only_go_back_to_donors() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut donor = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.deadline = NOW - 1;
+        campaign_data.amount_donated = 1_000;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + 1_000, campaign_data);
+        let mut registry = admin_registry(&program_id, &admin.key);
+
+        let accounts = [writing.info(), admin.info(), donor.info()];
+        let admin_accounts = [accounts[0].clone(), accounts[1].clone(), registry.info()];
+        let to_admin = [accounts[0].clone(), accounts[1].clone(), accounts[1].clone()];
+        assert_eq!(enable_refunds(&program_id, &admin_accounts), Ok(()));
+
+        assert_eq!(
+            refund(&program_id, &to_admin, RefundRequest { amount: 1_000 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(close_campaign(&program_id, &admin_accounts), Err(CrowdfundingError::GoalNotReached.into()));
+        assert_eq!(refund(&program_id, &accounts, RefundRequest { amount: 1_000 }), Ok(()));
+        assert_eq!(accounts[2].lamports(), 1_000);
+        assert_eq!(close_campaign(&program_id, &admin_accounts), Ok(()));
+    }
+
+    #[test]
+    fn close_campaign_only_gives_back_the_rent() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.deadline = NOW - 1;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1, campaign_data);
+        let mut registry = admin_registry(&program_id, &admin.key);
+        AdminRegistry { campaign_count: 3 }.serialize(&mut &mut registry.data[..]).unwrap();
+
+        let accounts = [writing.info(), admin.info(), registry.info()];
+        assert_eq!(close_campaign(&program_id, &accounts), Err(CrowdfundingError::FundsRemaining.into()));
+        **accounts[0].lamports.borrow_mut() -= 1;
+        assert_eq!(close_campaign(&program_id, &accounts), Ok(()));
+        drop(accounts);
+
+        assert_eq!(admin.lamports, rent_exemption + RENT_BUFFER_LAMPORTS);
+        assert_eq!(writing.lamports, 0);
+        assert_eq!(AdminRegistry::try_from_slice(&registry.data).unwrap().campaign_count, 2);
+    }
+
+    #[test]
+    fn ended_campaign_releases_its_registry_slot_once() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption, campaign_data);
+        let mut registry = admin_registry(&program_id, &admin.key);
+        AdminRegistry { campaign_count: 2 }.serialize(&mut &mut registry.data[..]).unwrap();
+
+        let accounts = [writing.info(), admin.info(), registry.info()];
+        assert_eq!(end_campaign(&program_id, &accounts), Ok(()));
+        assert_eq!(end_campaign(&program_id, &accounts), Ok(()));
+        assert_eq!(close_campaign(&program_id, &accounts), Ok(()));
+        drop(accounts);
+
+        assert_eq!(AdminRegistry::try_from_slice(&registry.data).unwrap().campaign_count, 1);
+    }
+
+    #[test]
+    fn failed_all_or_nothing_pays_nothing_to_the_admin_side() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.deadline = NOW - 1;
+        campaign_data.amount_donated = 1_000;
+        campaign_data.rent_reserved = Rent::default().minimum_balance(campaign_account_size());
+        let mut target = TestAccount::new(&program_id, 5_000_000, campaign(admin.key).try_to_vec().unwrap());
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000, campaign_data);
+
+        let accounts = [writing.info(), admin.info(), target.info()];
+        let admin_accounts = [accounts[0].clone(), accounts[1].clone()];
+        let to_admin = [accounts[0].clone(), accounts[1].clone(), accounts[1].clone()];
+        assert_eq!(
+            refund(&program_id, &to_admin, RefundRequest { amount: 1_000 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            fork_funds(&program_id, &accounts, ForkFundsRequest { amount: 1_000 }),
+            Err(CrowdfundingError::GoalNotReached.into())
+        );
+        assert_eq!(
+            withdraw_surplus(&program_id, &[accounts[0].clone(), accounts[1].clone(), accounts[2].clone(), accounts[2].clone()]),
+            Err(CrowdfundingError::GoalNotReached.into())
+        );
+        assert_eq!(
+            extend_deadline(&program_id, &admin_accounts, ExtendDeadlineRequest { new_deadline: NOW + 3600 }),
+            Err(CrowdfundingError::GoalNotReached.into())
+        );
+        assert_eq!(close_campaign(&program_id, &accounts), Err(CrowdfundingError::GoalNotReached.into()));
+        drop((accounts, admin_accounts, to_admin));
+
+        assert_eq!(writing.lamports, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000);
+    }
+
+    #[test]
+    fn flagged_campaign_can_not_refund_nor_close() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut donor = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.deadline = NOW - 1;
+        campaign_data.amount_donated = 1_000;
+        campaign_data.status = CampaignStatus::Flagged as u8;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info(), donor.info()];
+        assert_eq!(
+            refund(&program_id, &accounts, RefundRequest { amount: 1_000 }),
+            Err(CrowdfundingError::CampaignFlagged.into())
+        );
+        assert_eq!(close_campaign(&program_id, &accounts), Err(CrowdfundingError::CampaignFlagged.into()));
+        drop(accounts);
+
+        assert_eq!(writing.lamports, 5_000_000);
+        assert_eq!(donor.lamports, 0);
+    }
+
+    #[test]
+    fn extend_deadline_only_moves_it_later() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.deadline = NOW - 100;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info()];
+        assert_eq!(
+            extend_deadline(&program_id, &accounts, ExtendDeadlineRequest { new_deadline: NOW - 200 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            extend_deadline(&program_id, &accounts, ExtendDeadlineRequest { new_deadline: NOW - 50 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            extend_deadline(&program_id, &accounts, ExtendDeadlineRequest { new_deadline: NOW + 3600 }),
+            Ok(())
+        );
+        assert_eq!(
+            extend_deadline(&program_id, &accounts, ExtendDeadlineRequest { new_deadline: NOW + 1800 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.deadline, NOW + 3600);
+    }
+
+    #[test]
+    fn is_admin_tells_the_admin_apart() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut other = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.clone());
+
+        let accounts = [writing.info(), admin.info(), other.info()];
+        assert_eq!(is_admin(&program_id, &accounts[..2]), Ok(()));
+        assert_eq!(get_return_data().unwrap().1, vec![1]);
+        assert_eq!(is_admin(&program_id, &[accounts[0].clone(), accounts[2].clone()]), Ok(()));
+        assert_eq!(get_return_data().unwrap().1, vec![0]);
+        drop(accounts);
+
+        assert_eq!(writing.data, campaign_data);
+    }
+
+    #[test]
+    fn reconcile_counts_lamports_sent_straight_to_the_campaign() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut other = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.amount_donated = 1_000;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + 1_000, campaign_data);
+        /*
+         * a plain transfer to the campaign address, not through donate.
+         */
+        writing.lamports += 500;
+
+        let accounts = [writing.info(), other.info(), admin.info()];
+        let other_accounts = [accounts[0].clone(), accounts[1].clone()];
+        let admin_accounts = [accounts[0].clone(), accounts[2].clone()];
+        assert_eq!(reconcile(&program_id, &other_accounts), Err(CrowdfundingError::WrongAdmin.into()));
+        assert_eq!(reconcile(&program_id, &admin_accounts), Ok(()));
+        drop((accounts, other_accounts, admin_accounts));
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.amount_donated, 1_500);
+        assert_eq!(writing.lamports, rent_exemption + 1_500);
+    }
+
+    #[test]
+    fn reconcile_never_lowers_the_donations_nor_touches_all_or_nothing() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.amount_donated = 1_000;
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.try_to_vec().unwrap().len());
+        /*
+         * 600 lamports of the donations were already withdrawn.
+         */
+        let mut writing = TestAccount::new(&program_id, rent_exemption + 400, campaign_data.try_to_vec().unwrap());
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.deadline = NOW + 3600;
+        let mut all_or_nothing = TestAccount::new(&program_id, rent_exemption + 5_000, campaign_data.try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info(), all_or_nothing.info()];
+        assert_eq!(reconcile(&program_id, &accounts), Ok(()));
+        assert_eq!(
+            reconcile(&program_id, &[accounts[2].clone(), accounts[1].clone()]),
+            Err(ProgramError::InvalidArgument)
+        );
+        drop(accounts);
+
+        assert_eq!(CampaignDetails::try_from_slice(&writing.data).unwrap().amount_donated, 1_000);
+        assert_eq!(CampaignDetails::try_from_slice(&all_or_nothing.data).unwrap().amount_donated, 1_000);
+    }
+
+    #[test]
+    fn fork_funds_moves_lamports_and_donations_between_campaigns() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut other = TestAccount::signer(0);
+        let mut source_data = campaign(admin.key);
+        source_data.amount_donated = 1_000;
+        source_data.max_withdraw_per_day = 700;
+        let source_data = source_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(source_data.len());
+        let mut source = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000, source_data);
+        let mut target_data = campaign(other.key);
+        target_data.amount_donated = 200;
+        let mut target = TestAccount::new(&program_id, 5_000_000, target_data.try_to_vec().unwrap());
+        let mut paused_data = campaign(other.key);
+        paused_data.status = CampaignStatus::Paused as u8;
+        let mut paused = TestAccount::new(&program_id, 5_000_000, paused_data.try_to_vec().unwrap());
+
+        let accounts = [source.info(), target.info(), admin.info(), other.info(), paused.info()];
+        let admin_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[1].clone()];
+        let other_accounts = [accounts[0].clone(), accounts[3].clone(), accounts[1].clone()];
+        let paused_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[4].clone()];
+        assert_eq!(
+            fork_funds(&program_id, &other_accounts, ForkFundsRequest { amount: 600 }),
+            Err(CrowdfundingError::WrongAdmin.into())
+        );
+        assert_eq!(
+            fork_funds(&program_id, &admin_accounts, ForkFundsRequest { amount: 1_001 }),
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        );
+        assert_eq!(
+            fork_funds(&program_id, &paused_accounts, ForkFundsRequest { amount: 600 }),
+            Err(CrowdfundingError::CampaignPaused.into())
+        );
+        assert_eq!(fork_funds(&program_id, &admin_accounts, ForkFundsRequest { amount: 600 }), Ok(()));
+        /*
+         * the fork counts against the daily cap of the source like a withdrawal.
+         */
+        assert_eq!(
+            fork_funds(&program_id, &admin_accounts, ForkFundsRequest { amount: 200 }),
+            Err(CrowdfundingError::DailyWithdrawLimitExceeded.into())
+        );
+        drop((accounts, admin_accounts, other_accounts, paused_accounts));
+
+        assert_eq!(source.lamports, rent_exemption + RENT_BUFFER_LAMPORTS + 400);
+        assert_eq!(target.lamports, 5_000_600);
+        assert_eq!(CampaignDetails::try_from_slice(&source.data).unwrap().amount_donated, 400);
+        assert_eq!(CampaignDetails::try_from_slice(&target.data).unwrap().amount_donated, 800);
+    }
+
+    #[test]
+    fn withdraw_surplus_only_takes_the_rent_no_longer_needed() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut platform = platform_account();
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        campaign_data.amount_donated = 1_000;
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.try_to_vec().unwrap().len());
+        /*
+         * created when the rent_exemption was 300 lamports higher than it is now,
+         * the 500 lamports sent straight to the account are not surplus.
+         */
+        campaign_data.rent_reserved = rent_exemption + 300;
+        let mut writing = TestAccount::new(
+            &program_id,
+            rent_exemption + 300 + RENT_BUFFER_LAMPORTS + 1_000 + 500,
+            campaign_data.try_to_vec().unwrap(),
+        );
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(withdraw_surplus(&program_id, &accounts), Ok(()));
+        assert_eq!(get_return_data().unwrap().1, 300u64.to_le_bytes().to_vec());
+        assert_eq!(withdraw_surplus(&program_id, &accounts), Err(ProgramError::InsufficientFunds));
+        /*
+         * without surplus the withdraw checks don't run, a flagged campaign is not even reported.
+         */
+        let mut campaign_data = load_campaign(&accounts[0]).unwrap();
+        campaign_data.status = CampaignStatus::Flagged as u8;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(withdraw_surplus(&program_id, &accounts), Err(ProgramError::InsufficientFunds));
+        drop(accounts);
+
+        assert_eq!(writing.lamports, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000 + 500);
+        assert_eq!(beneficiary.lamports, 300);
+        assert_eq!(admin.lamports, 0);
+        assert_eq!(CampaignDetails::try_from_slice(&writing.data).unwrap().rent_reserved, rent_exemption);
+    }
+
+    #[test]
+    fn set_target_stays_at_or_above_the_donations() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 600;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info()];
+        assert_eq!(set_target(&program_id, &accounts, SetTargetRequest { target_amount: 2_000 }), Ok(()));
+        assert_eq!(load_campaign(&accounts[0]).unwrap().target_amount, 2_000);
+        assert_eq!(
+            set_target(&program_id, &accounts, SetTargetRequest { target_amount: 599 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(set_target(&program_id, &accounts, SetTargetRequest { target_amount: 600 }), Ok(()));
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.target_amount, 600);
+        assert_eq!(remaining_to_goal(&stored), 0);
+    }
+
+    #[test]
+    fn set_target_never_lowers_an_all_or_nothing_goal() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.deadline = NOW - 1;
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 600;
+        let mut writing = TestAccount::new(&program_id, 5_000_000, campaign_data.try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info()];
+        assert_eq!(
+            set_target(&program_id, &accounts, SetTargetRequest { target_amount: 600 }),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(set_target(&program_id, &accounts, SetTargetRequest { target_amount: 2_000 }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(CampaignDetails::try_from_slice(&writing.data).unwrap().target_amount, 2_000);
+    }
+
+    #[test]
+    fn withdraw_pays_beneficiary_minus_platform_fee() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(withdraw(&program_id, &accounts, WithdrawRequest { amount: 1_000_000 }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(platform_fee(1_000_000), 10_000);
+        assert_eq!(writing.lamports, rent_exemption + RENT_BUFFER_LAMPORTS);
+        assert_eq!(platform.lamports, 10_000);
+        assert_eq!(beneficiary.lamports, 990_000);
+        assert_eq!(admin.lamports, 0);
+    }
+
+    #[test]
+    fn withdraw_needs_the_required_signers() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut co_signer = TestAccount::signer(0);
+        let mut absent_signer = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        campaign_data.signers = vec![admin.key, co_signer.key, absent_signer.key];
+        campaign_data.required_signers = 3;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [
+            writing.info(),
+            admin.info(),
+            platform.info(),
+            beneficiary.info(),
+            co_signer.info(),
+            absent_signer.info(),
+        ];
+        assert_eq!(
+            withdraw(&program_id, &accounts[..4], WithdrawRequest { amount: 1_000 }),
+            Err(CrowdfundingError::NotEnoughSigners.into())
+        );
+        assert_eq!(
+            withdraw(&program_id, &accounts, WithdrawRequest { amount: 1_000 }),
+            Err(CrowdfundingError::NotEnoughSigners.into())
+        );
+
+        let mut campaign_data = load_campaign(&accounts[0]).unwrap();
+        campaign_data.required_signers = 2;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(withdraw(&program_id, &accounts, WithdrawRequest { amount: 1_000 }), Ok(()));
+        drop(accounts);
+
+        assert_eq!(beneficiary.lamports, 990);
+    }
+
+    #[test]
+    fn withdraw_split_and_refund_need_the_required_signers() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut co_signer = TestAccount::signer(0);
+        let mut recipient = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.amount_donated = 2_000;
+        campaign_data.signers = vec![admin.key, co_signer.key];
+        campaign_data.required_signers = 2;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 2_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), recipient.info(), co_signer.info()];
+        let split = || WithdrawSplitRequest { amounts: vec![1_000] };
+        assert_eq!(
+            withdraw_split(&program_id, &accounts[..4], split()),
+            Err(CrowdfundingError::NotEnoughSigners.into())
+        );
+        assert_eq!(withdraw_split(&program_id, &accounts, split()), Ok(()));
+
+        /*
+         * refunds only go out of a failed all-or-nothing campaign.
+         */
+        let refund_accounts = [accounts[0].clone(), accounts[1].clone(), accounts[3].clone(), accounts[4].clone()];
+        assert_eq!(
+            refund(&program_id, &refund_accounts, RefundRequest { amount: 500 }),
+            Err(CrowdfundingError::RefundsNotAllowed.into())
+        );
+        let mut campaign_data = load_campaign(&accounts[0]).unwrap();
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.deadline = NOW - 1;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(
+            refund(&program_id, &refund_accounts[..3], RefundRequest { amount: 500 }),
+            Err(CrowdfundingError::NotEnoughSigners.into())
+        );
+        assert_eq!(refund(&program_id, &refund_accounts, RefundRequest { amount: 500 }), Ok(()));
+        drop((accounts, refund_accounts));
+
+        assert_eq!(recipient.lamports, 1_490);
+    }
+
+    #[test]
+    fn confirm_withdraw_waits_for_the_unlock_time() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(confirm_withdraw(&program_id, &accounts), Err(ProgramError::InvalidArgument));
+        assert_eq!(request_withdraw(&program_id, &accounts[..2], WithdrawRequest { amount: 1_000_000 }), Ok(()));
+        let mut campaign_data = load_campaign(&accounts[0]).unwrap();
+        assert_eq!(campaign_data.withdraw_unlock_ts, NOW + WITHDRAW_DELAY_SECONDS);
+        assert_eq!(
+            confirm_withdraw(&program_id, &accounts),
+            Err(CrowdfundingError::ScheduledWithdrawalNotDue.into())
+        );
+        assert_eq!(accounts[3].lamports(), 0);
+
+        /*
+         * the delay is over.
+         */
+        campaign_data.withdraw_unlock_ts = NOW;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(confirm_withdraw(&program_id, &accounts), Ok(()));
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.pending_withdraw_amount, 0);
+        assert_eq!(beneficiary.lamports, 990_000);
+        assert_eq!(platform.lamports, 10_000);
+    }
+
+    #[test]
+    fn withdrawals_above_the_timelock_threshold_must_be_requested() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        campaign_data.timelock_threshold = 400_000;
+        campaign_data.per_interval_amount = 400_000;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        let time_locked: ProgramResult = Err(CrowdfundingError::WithdrawalTimeLocked.into());
+        assert_eq!(withdraw(&program_id, &accounts, WithdrawRequest { amount: 400_000 }), time_locked);
+        assert_eq!(can_withdraw(&program_id, &accounts, WithdrawRequest { amount: 400_000 }), time_locked);
+        assert_eq!(withdraw_all(&program_id, &accounts), time_locked);
+        assert_eq!(withdraw_split(&program_id, &accounts, WithdrawSplitRequest { amounts: vec![400_000] }), time_locked);
+        let scheduled_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[3].clone()];
+        assert_eq!(execute_scheduled_withdrawal(&program_id, &scheduled_accounts), time_locked);
+        assert_eq!(withdraw(&program_id, &accounts, WithdrawRequest { amount: 399_999 }), Ok(()));
+
+        /*
+         * the same amount goes through once requested and its delay is over.
+         */
+        assert_eq!(request_withdraw(&program_id, &accounts[..2], WithdrawRequest { amount: 400_000 }), Ok(()));
+        let mut campaign_data = load_campaign(&accounts[0]).unwrap();
+        campaign_data.withdraw_unlock_ts = NOW;
+        write_campaign(&accounts[0], &campaign_data).unwrap();
+        assert_eq!(confirm_withdraw(&program_id, &accounts), Ok(()));
+        drop((accounts, scheduled_accounts));
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.pending_withdraw_amount, 0);
+        assert_eq!(stored.withdrawn_today, 799_999);
+        assert_eq!(beneficiary.lamports + platform.lamports, 799_999);
+    }
+
+    #[test]
+    fn cancelled_withdrawal_can_not_be_confirmed() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(request_withdraw(&program_id, &accounts[..2], WithdrawRequest { amount: 1_000_000 }), Ok(()));
+        assert_eq!(cancel_withdraw(&program_id, &accounts[..2]), Ok(()));
+        assert_eq!(cancel_withdraw(&program_id, &accounts[..2]), Err(ProgramError::InvalidArgument));
+        assert_eq!(confirm_withdraw(&program_id, &accounts), Err(ProgramError::InvalidArgument));
+        drop(accounts);
+
+        assert_eq!(beneficiary.lamports, 0);
+        assert_eq!(writing.lamports, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000_000);
+    }
+
+    #[test]
+    fn withdraw_below_the_fee_free_threshold_pays_no_fee() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.beneficiary = Pubkey::new_unique();
+
+        assert_eq!(
+            run_withdraw(&program_id, &campaign_data, FEE_FREE_THRESHOLD, FEE_FREE_THRESHOLD - 1),
+            (Ok(()), FEE_FREE_THRESHOLD - 1)
+        );
+        assert_eq!(
+            run_withdraw(&program_id, &campaign_data, FEE_FREE_THRESHOLD, FEE_FREE_THRESHOLD),
+            (Ok(()), FEE_FREE_THRESHOLD - platform_fee(FEE_FREE_THRESHOLD))
+        );
+        assert_eq!(platform_fee(FEE_FREE_THRESHOLD), 1);
+    }
+
+    #[test]
+    fn split_and_scheduled_withdrawals_below_the_fee_free_threshold_pay_no_fee() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut campaign_data = campaign(admin.key);
+        campaign_data.beneficiary = beneficiary.key;
+        campaign_data.per_interval_amount = FEE_FREE_THRESHOLD - 1;
+        let campaign_data = campaign_data.try_to_vec().unwrap();
+        let rent_exemption = Rent::default().minimum_balance(campaign_data.len());
+        let mut writing = TestAccount::new(&program_id, rent_exemption + RENT_BUFFER_LAMPORTS + 1_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        let split = WithdrawSplitRequest { amounts: vec![FEE_FREE_THRESHOLD - 1] };
+        assert_eq!(withdraw_split(&program_id, &accounts, split), Ok(()));
+        let scheduled_accounts = [accounts[0].clone(), accounts[2].clone(), accounts[3].clone()];
+        assert_eq!(execute_scheduled_withdrawal(&program_id, &scheduled_accounts), Ok(()));
+        drop((accounts, scheduled_accounts));
+
+        assert_eq!(beneficiary.lamports, 2 * (FEE_FREE_THRESHOLD - 1));
+        assert_eq!(platform.lamports, 0);
+    }
+
+    #[test]
+    fn withdraw_rejects_wrong_beneficiary_account() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut other = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 1_000_000_000, campaign_data);
+        let mut platform = platform_account();
+
+        let accounts = [writing.info(), admin.info(), platform.info(), other.info()];
+        assert_eq!(
+            withdraw(&program_id, &accounts, WithdrawRequest { amount: 1 }),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn all_or_nothing_withdraw_waits_for_deadline_and_goal() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.campaign_type = CampaignType::AllOrNothing as u8;
+        campaign_data.amount_donated = campaign_data.target_amount;
+        assert_eq!(
+            run_withdraw(&program_id, &campaign_data, 1_000, 1_000),
+            (Err(CrowdfundingError::CampaignStillActive.into()), 0)
+        );
+
+        campaign_data.deadline = NOW - 1;
+        campaign_data.amount_donated = campaign_data.target_amount - 1;
+        assert_eq!(
+            run_withdraw(&program_id, &campaign_data, 1_000, 1_000),
+            (Err(CrowdfundingError::GoalNotReached.into()), 0)
+        );
+
+        campaign_data.amount_donated = campaign_data.target_amount;
+        assert_eq!(run_withdraw(&program_id, &campaign_data, 1_000, 1_000), (Ok(()), 990));
+    }
+
+    #[test]
+    fn flexible_withdraw_works_before_deadline_and_goal() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let campaign_data = campaign(Pubkey::new_unique());
+        assert_eq!(run_withdraw(&program_id, &campaign_data, 1_000, 1_000), (Ok(()), 990));
+    }
+
+    #[test]
+    fn is_overfunded_only_above_the_target() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 999;
+        assert!(!is_overfunded(&campaign_data));
+        campaign_data.amount_donated = 1_000;
+        assert!(!is_overfunded(&campaign_data));
+        assert_eq!(progress_bps(&campaign_data), 10_000);
+        campaign_data.amount_donated = 1_001;
+        assert!(is_overfunded(&campaign_data));
+        assert_eq!(progress_bps(&campaign_data), 10_000);
+
+        campaign_data.target_amount = 0;
+        assert!(!is_overfunded(&campaign_data));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn lamports_to_sol_string_trims_trailing_zeros() {
+        assert_eq!(lamports_to_sol_string(0), "0");
+        assert_eq!(lamports_to_sol_string(2_000_000_000), "2");
+        assert_eq!(lamports_to_sol_string(1_500_000_000), "1.5");
+        assert_eq!(lamports_to_sol_string(1), "0.000000001");
+        assert_eq!(lamports_to_sol_string(123_456_789_010), "123.45678901");
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn view_computes_progress_remaining_and_expiry() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 250;
+        campaign_data.deadline = NOW + 10;
+
+        let campaign_view = view(&campaign_data, NOW);
+        assert_eq!(campaign_view.name, campaign_data.name);
+        assert_eq!(campaign_view.amount_donated, 250);
+        assert_eq!(campaign_view.progress_bps, 2_500);
+        assert_eq!(campaign_view.remaining_to_goal, 750);
+        assert!(!campaign_view.is_expired);
+
+        campaign_data.amount_donated = 1_500;
+        let campaign_view = view(&campaign_data, NOW + 11);
+        assert_eq!(campaign_view.progress_bps, 10_000);
+        assert_eq!(campaign_view.remaining_to_goal, 0);
+        assert!(campaign_view.is_expired);
+    }
+
+    #[test]
+    fn is_expired_only_after_the_deadline() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.deadline = 0;
+        assert!(!is_expired(&campaign_data, NOW));
+        campaign_data.deadline = NOW;
+        assert!(!is_expired(&campaign_data, NOW - 1));
+        assert!(!is_expired(&campaign_data, NOW));
+        assert!(is_expired(&campaign_data, NOW + 1));
+    }
+
+    #[test]
+    fn remaining_to_goal_under_at_and_over_the_goal() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 400;
+        assert_eq!(remaining_to_goal(&campaign_data), 600);
+        campaign_data.amount_donated = 1_000;
+        assert_eq!(remaining_to_goal(&campaign_data), 0);
+        campaign_data.amount_donated = 5_000;
+        assert_eq!(remaining_to_goal(&campaign_data), 0);
+    }
+
+    #[test]
+    fn compute_match_is_clamped_to_the_pool() {
+        assert_eq!(compute_match(700, 500), 500);
+        assert_eq!(compute_match(300, 500), 300);
+        assert_eq!(compute_match(500, 500), 500);
+        assert_eq!(compute_match(u64::MAX, 1), 1);
+        assert_eq!(compute_match(1, u64::MAX), 1);
+        assert_eq!(compute_match(500, 0), 0);
+    }
+
+    #[test]
+    fn progress_bps_of_zero_partial_and_over_funded_campaigns() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.target_amount = 0;
+        campaign_data.amount_donated = 500;
+        assert_eq!(progress_bps(&campaign_data), 0);
+
+        campaign_data.target_amount = 1_000;
+        campaign_data.amount_donated = 0;
+        assert_eq!(progress_bps(&campaign_data), 0);
+        campaign_data.amount_donated = 255;
+        assert_eq!(progress_bps(&campaign_data), 2_550);
+        campaign_data.amount_donated = 1_000;
+        assert_eq!(progress_bps(&campaign_data), 10_000);
+        campaign_data.amount_donated = u64::MAX;
+        assert_eq!(progress_bps(&campaign_data), 10_000);
+    }
+
+    #[test]
+    fn daily_withdrawal_cap_resets_on_a_new_day() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.max_withdraw_per_day = 1_000;
+        let day_end = (NOW / SECONDS_PER_DAY + 1) * SECONDS_PER_DAY - 1;
+
+        assert_eq!(record_daily_withdrawal(&mut campaign_data, 600, day_end - 10), Ok(()));
+        assert_eq!(
+            record_daily_withdrawal(&mut campaign_data, 500, day_end),
+            Err(CrowdfundingError::DailyWithdrawLimitExceeded.into())
+        );
+        assert_eq!(campaign_data.withdrawn_today, 600);
+
+        assert_eq!(record_daily_withdrawal(&mut campaign_data, 500, day_end + 1), Ok(()));
+        assert_eq!(campaign_data.withdrawn_today, 500);
+        assert_eq!(
+            record_daily_withdrawal(&mut campaign_data, 501, day_end + 2),
+            Err(CrowdfundingError::DailyWithdrawLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn daily_withdrawal_cap_of_zero_is_unlimited() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        assert_eq!(record_daily_withdrawal(&mut campaign_data, u64::MAX, NOW), Ok(()));
+    }
+
+    #[test]
+    fn old_admin_cannot_withdraw_after_transfer_admin() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let new_admin = Pubkey::new_unique();
+        let campaign_data = campaign(admin.key).try_to_vec().unwrap();
+        let mut writing = TestAccount::new(&program_id, 1_000_000_000, campaign_data);
+        let mut platform = platform_account();
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+        let mut registry = admin_registry(&program_id, &admin.key);
+        AdminRegistry { campaign_count: 1 }.serialize(&mut &mut registry.data[..]).unwrap();
+        let mut new_registry = admin_registry(&program_id, &new_admin);
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        let transfer_accounts = [accounts[0].clone(), accounts[1].clone(), registry.info(), new_registry.info()];
+        assert_eq!(
+            transfer_admin(&program_id, &transfer_accounts, TransferAdminRequest { new_admin }),
+            Ok(())
+        );
+        assert_eq!(
+            withdraw(&program_id, &accounts, WithdrawRequest { amount: 1 }),
+            Err(CrowdfundingError::WrongAdmin.into())
+        );
+        drop((accounts, transfer_accounts));
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.admin, new_admin);
+        assert_eq!(AdminRegistry::try_from_slice(&registry.data).unwrap().campaign_count, 0);
+        assert_eq!(AdminRegistry::try_from_slice(&new_registry.data).unwrap().campaign_count, 1);
+    }
+
+    #[test]
+    fn migrate_v0_produces_current_version_account() {
+        let admin = Pubkey::new_unique();
+        let old = CampaignDetailsV0 {
+            admin,
+            name: String::from("campaign"),
+            description: String::from("description"),
+            image_link: String::from("https://example.com/image.png"),
+            amount_donated: 42,
+        }
+        .try_to_vec()
+        .unwrap();
+
+        let migrated = migrate_v0(&old).unwrap().try_to_vec().unwrap();
+        assert_eq!(check_campaign_version(&migrated), Ok(()));
+        let stored = CampaignDetails::try_from_slice(&migrated).unwrap();
+        assert_eq!(stored.version, CAMPAIGN_VERSION);
+        assert_eq!(stored.admin, admin);
+        assert_eq!(stored.name, "campaign");
+        assert_eq!(stored.amount_donated, 42);
+        assert_eq!(stored.deadline, 0);
+
+        assert_eq!(migrate_v0(&migrated).unwrap_err(), ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn malformed_instruction_data_is_an_error() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut writing = TestAccount::new(&program_id, 1_000_000_000, vec![]);
+
+        let accounts = [writing.info(), admin.info()];
+        for instruction_data in [&[][..], &[0, 1, 2][..], &[1, 1][..], &[2, 0][..], &[255][..]] {
+            assert_eq!(
+                process_instruction(&program_id, &accounts, instruction_data),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        }
+    }
+
+    #[test]
+    fn too_few_accounts_is_an_error() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut writing = TestAccount::new(&program_id, 1_000_000_000, campaign(admin.key).try_to_vec().unwrap());
+
+        let accounts = [writing.info(), admin.info()];
+        assert_eq!(
+            withdraw(&program_id, &accounts, WithdrawRequest { amount: 1 }),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            can_withdraw(&program_id, &accounts, WithdrawRequest { amount: 1 }),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            donate(&program_id, &accounts, DonateRequest { donor: Pubkey::new_unique(), anonymous: true }),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            create_campain(&program_id, &accounts, campaign(Pubkey::new_unique())),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn validate_instruction_len_matches_the_smallest_payloads() {
+        let mut tag = 0;
+        while validate_instruction_len(tag, &[0; 1024]).is_ok() {
+            let mut min_len = 0;
+            while validate_instruction_len(tag, &vec![0; min_len]).is_err() {
+                min_len += 1;
+            }
+            /*
+             * an all zero payload of the minimum length is a valid instruction (empty strings, zero amounts).
+             */
+            let mut instruction_data = vec![tag];
+            instruction_data.extend(vec![0; min_len]);
+            assert!(CrowdfundingInstruction::try_from_slice(&instruction_data).is_ok(), "{}", tag);
+            tag += 1;
+        }
+        assert!(CrowdfundingInstruction::try_from_slice(&[tag]).is_err());
+        assert_eq!(validate_instruction_len(1, &[0; 7]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn tag_without_payload_is_rejected_before_the_handler() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(process_instruction(&program_id, &[], &[0]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(process_instruction(&program_id, &[], &[1]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(validate_instruction_len(5, &[]), Ok(()));
+    }
+
+    #[test]
+    fn malformed_campaign_account_is_an_error() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::signer(0);
+        let mut writing = TestAccount::new(&program_id, 1_000_000_000, vec![CAMPAIGN_VERSION, 1, 2, 3]);
+        let mut platform = platform_account();
+        let mut beneficiary = TestAccount::new(&Pubkey::default(), 0, vec![]);
+
+        let accounts = [writing.info(), admin.info(), platform.info(), beneficiary.info()];
+        assert_eq!(
+            withdraw(&program_id, &accounts, WithdrawRequest { amount: 1 }),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn pay_creation_fee_credits_the_treasury() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(5_000);
+        let mut treasury = TestAccount {
+            key: treasury_pda(&program_id).0,
+            ..TestAccount::new(&system_program::id(), 1_000_000, vec![])
+        };
+        let mut not_treasury = TestAccount::new(&system_program::id(), 0, vec![]);
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+
+        let accounts = [creator.info(), treasury.info(), not_treasury.info(), system.info()];
+        assert_eq!(
+            pay_creation_fee(&program_id, &accounts[0], Some(&accounts[2]), Some(&accounts[3]), 2_000),
+            Err(ProgramError::InvalidSeeds)
+        );
+        assert_eq!(pay_creation_fee(&program_id, &accounts[0], None, None, 0), Ok(()));
+        assert_eq!(pay_creation_fee(&program_id, &accounts[0], Some(&accounts[1]), Some(&accounts[3]), 2_000), Ok(()));
+        drop(accounts);
+
+        assert_eq!(treasury.lamports, 1_002_000);
+        assert_eq!(creator.lamports, 3_000);
+        assert_eq!(not_treasury.lamports, 0);
+    }
+
+    #[test]
+    fn create_campain_writes_to_the_campaign_pda_only() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let admin = creator.key;
+        /*
+         * a creator trying to launch with a fake backer and a withdrawal schedule.
+         */
+        let mut input_data = campaign(admin);
+        input_data.last_donor = Pubkey::new_unique();
+        input_data.largest_donor = input_data.last_donor;
+        input_data.largest_donation = 1_000_000;
+        input_data.next_withdraw_ts = NOW;
+        input_data.interval_seconds = 60;
+        input_data.per_interval_amount = 1_000;
+        /*
+         * the name is stored a second time as seed_name.
+         */
+        let space = input_data.try_to_vec().unwrap().len() + input_data.name.len();
+
+        let mut wrong = TestAccount::new(&program_id, 1_000_000_000, vec![0; space]);
+        let accounts = [wrong.info(), creator.info(), registry.info()];
+        assert_eq!(
+            create_campain(&program_id, &accounts, campaign(admin)),
+            Err(ProgramError::InvalidSeeds)
+        );
+        drop(accounts);
+
+        let (campaign_address, bump) = campaign_pda(&program_id, &admin, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; space])
+        };
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(create_campain(&program_id, &accounts, input_data.clone()), Ok(()));
+        assert_eq!(
+            create_campain(&program_id, &accounts, campaign(admin)),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+        drop(accounts);
+
+        let stored = CampaignDetails::try_from_slice(&writing.data).unwrap();
+        assert_eq!(stored.admin, admin);
+        assert_eq!(stored.version, CAMPAIGN_VERSION);
+        assert_eq!(stored.created_at, NOW);
+        assert_eq!(stored.seed_admin, admin);
+        assert_eq!(stored.seed_name, input_data.name);
+        assert_eq!(stored.bump, bump);
+        assert_eq!(stored.last_donor, Pubkey::default());
+        assert_eq!((stored.largest_donor, stored.largest_donation), (Pubkey::default(), 0));
+        assert_eq!((stored.next_withdraw_ts, stored.interval_seconds, stored.per_interval_amount), (0, 0, 0));
+    }
+
+    #[test]
+    fn create_campain_returns_the_campaign_address_and_bump() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let input_data = campaign(creator.key);
+        let space = input_data.try_to_vec().unwrap().len() + input_data.name.len();
+        let (campaign_address, bump) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; space])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Ok(()));
+        let (_, return_data) = get_return_data().unwrap();
+        assert_eq!(return_data.len(), 33);
+        assert_eq!(Pubkey::new(&return_data[..32]), campaign_address);
+        assert_eq!(return_data[32], bump);
+    }
+
+    #[test]
+    fn create_campain_is_limited_per_admin() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let admin = creator.key;
+
+        for count in 0..=MAX_CAMPAIGNS_PER_ADMIN {
+            let mut input_data = campaign(admin);
+            input_data.name = format!("campaign {}", count);
+            let (campaign_address, _) = campaign_pda(&program_id, &admin, &input_data.name);
+            let mut writing = TestAccount {
+                key: campaign_address,
+                ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+            };
+            let accounts = [writing.info(), creator.info(), registry.info()];
+            let expected = if count < MAX_CAMPAIGNS_PER_ADMIN {
+                Ok(())
+            } else {
+                Err(ProgramError::from(CrowdfundingError::TooManyCampaigns))
+            };
+            assert_eq!(create_campain(&program_id, &accounts, input_data), expected);
+        }
+        let stored = AdminRegistry::try_from_slice(&registry.data).unwrap();
+        assert_eq!(stored.campaign_count, MAX_CAMPAIGNS_PER_ADMIN);
+    }
+
+    #[test]
+    fn create_campain_takes_over_a_pre_funded_registry() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let space = AdminRegistry::default().try_to_vec().unwrap().len();
+        let rent_exemption = Rent::default().minimum_balance(space);
+        let mut creator = TestAccount::signer(rent_exemption);
+        let mut registry = TestAccount {
+            key: admin_registry_pda(&program_id, &creator.key).0,
+            ..TestAccount::new(&Pubkey::default(), 1, vec![0; space])
+        };
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+        let input_data = campaign(creator.key);
+        let mut writing = TestAccount {
+            key: campaign_pda(&program_id, &creator.key, &input_data.name).0,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info(), system.info()];
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Ok(()));
+        drop(accounts);
+
+        /*
+         * the lamports someone sent to the address first count towards its rent.
+         */
+        assert_eq!(registry.lamports, rent_exemption);
+        assert_eq!(creator.lamports, 1);
+        assert_eq!(AdminRegistry::try_from_slice(&registry.data).unwrap().campaign_count, 1);
+    }
+
+    #[test]
+    fn create_campain_takes_over_a_pre_funded_campaign_address() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let rent_exemption = Rent::default().minimum_balance(campaign_account_size());
+        let mut creator = TestAccount::signer(rent_exemption);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+        let input_data = campaign(creator.key);
+        let mut writing = TestAccount {
+            key: campaign_pda(&program_id, &creator.key, &input_data.name).0,
+            ..TestAccount::new(&Pubkey::default(), 1_000, vec![0; campaign_account_size()])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info(), system.info()];
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Ok(()));
+        drop(accounts);
+
+        assert_eq!(writing.lamports, rent_exemption);
+        assert_eq!(creator.lamports, 1_000);
+        assert_eq!(CampaignDetails::deserialize(&mut &writing.data[..]).unwrap().admin, creator.key);
+    }
+
+    #[test]
+    fn create_campain_counts_every_campaign_in_the_global_state() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut system = TestAccount {
+            key: system_program::id(),
+            ..TestAccount::new(&Pubkey::default(), 0, vec![])
+        };
+        let (global_address, _) = global_state_pda(&program_id);
+        let mut global = TestAccount {
+            key: global_address,
+            ..TestAccount::new(&program_id, 1_000_000, GlobalState::default().try_to_vec().unwrap())
+        };
+
+        for count in 0..3 {
+            let mut creator = TestAccount::signer(0);
+            let mut registry = admin_registry(&program_id, &creator.key);
+            let input_data = campaign(creator.key);
+            let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+            let mut writing = TestAccount {
+                key: campaign_address,
+                ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+            };
+            let accounts = [writing.info(), creator.info(), registry.info(), system.info(), global.info()];
+            assert_eq!(create_campain(&program_id, &accounts, input_data), Ok(()));
+            drop(accounts);
+            assert_eq!(GlobalState::try_from_slice(&global.data).unwrap().total_campaigns, count + 1);
+        }
+    }
+
+    #[test]
+    fn create_campain_rejects_under_sized_account() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let input_data = campaign(creator.key);
+        let space = input_data.try_to_vec().unwrap().len();
+        let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; space - 1])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(
+            create_campain(&program_id, &accounts, input_data),
+            Err(CrowdfundingError::AccountDataTooSmall.into())
+        );
+    }
+
+    #[test]
+    fn create_campain_rejects_too_short_duration() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let mut input_data = campaign(creator.key);
+        input_data.deadline = NOW + MIN_CAMPAIGN_DURATION_SECONDS - 1;
+        let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn create_campain_takes_deadline_0_as_no_deadline() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let mut input_data = campaign(creator.key);
+        input_data.deadline = 0;
+        let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        let mut all_or_nothing = input_data.clone();
+        all_or_nothing.campaign_type = CampaignType::AllOrNothing as u8;
+        assert_eq!(create_campain(&program_id, &accounts, all_or_nothing), Err(ProgramError::InvalidArgument));
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Ok(()));
+        drop(accounts);
+
+        let stored = CampaignDetails::deserialize(&mut &writing.data[..]).unwrap();
+        assert_eq!(stored.deadline, 0);
+        assert!(!is_expired(&stored, i64::MAX));
+    }
+
+    #[test]
+    fn create_campain_only_takes_the_matcher_pda_and_an_empty_pool() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let mut input_data = campaign(creator.key);
+        let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+        };
+        input_data.match_pool_remaining = u64::MAX;
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        let mut other_matcher = input_data.clone();
+        other_matcher.matcher = Pubkey::new_unique();
+        assert_eq!(create_campain(&program_id, &accounts, other_matcher), Err(ProgramError::InvalidSeeds));
+        input_data.matcher = matcher_pda(&program_id, &campaign_address).0;
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Ok(()));
+        drop(accounts);
+
+        /*
+         * only fund_match_pool fills the pool.
+         */
+        let stored = CampaignDetails::deserialize(&mut &writing.data[..]).unwrap();
+        assert_eq!(stored.matcher, matcher_pda(&program_id, &campaign_address).0);
+        assert_eq!(stored.match_pool_remaining, 0);
+    }
+
+    #[test]
+    fn create_campain_rejects_target_above_the_maximum() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let mut input_data = campaign(creator.key);
+        input_data.target_amount = MAX_TARGET_AMOUNT + 1;
+        let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+        };
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(create_campain(&program_id, &accounts, input_data), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn create_campain_rejects_admin_other_than_the_creator() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let someone_else = Pubkey::new_unique();
+        let input_data = campaign(someone_else);
+        let space = input_data.try_to_vec().unwrap().len();
+        let (campaign_address, _) = campaign_pda(&program_id, &someone_else, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; space])
+        };
+        let instruction_data = CrowdfundingInstruction::CreateCampaign(Box::new(input_data)).try_to_vec().unwrap();
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &instruction_data),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        drop(accounts);
+        assert!(writing.data.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn create_campain_minimal_leaves_description_and_image_empty() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let admin = creator.key;
+        let name = String::from("bot campaign");
+        let (campaign_address, _) = campaign_pda(&program_id, &admin, &name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+        };
+        let instruction_data = CrowdfundingInstruction::CreateCampaignMinimal(MinimalCampaignRequest {
+            name: name.clone(),
+            admin,
+        })
+        .try_to_vec()
+        .unwrap();
+
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(process_instruction(&program_id, &accounts, &instruction_data), Ok(()));
+        drop(accounts);
+
+        let stored = load_campaign(&writing.info()).unwrap();
+        assert_eq!(stored.name, name);
+        assert_eq!(stored.admin, admin);
+        assert_eq!(stored.beneficiary, admin);
+        assert!(stored.description.is_empty());
+        assert!(stored.image_link.is_empty());
+    }
+
+    #[test]
+    fn create_campain_checks_the_image_link_scheme() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let admin = creator.key;
+
+        for (image_link, expected) in [
+            ("", Ok(())),
+            ("http://example.com/image.png", Ok(())),
+            ("https://example.com/image.png", Ok(())),
+            ("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi", Ok(())),
+            ("ftp://example.com/image.png", Err(ProgramError::from(CrowdfundingError::InvalidImageLink))),
+            ("example.com/image.png", Err(ProgramError::from(CrowdfundingError::InvalidImageLink))),
+            ("javascript:alert(1)", Err(ProgramError::from(CrowdfundingError::InvalidImageLink))),
+        ] {
+            let mut input_data = campaign(admin);
+            input_data.image_link = String::from(image_link);
+            let (campaign_address, _) = campaign_pda(&program_id, &admin, &input_data.name);
+            let mut writing = TestAccount {
+                key: campaign_address,
+                ..TestAccount::new(&program_id, 1_000_000_000, vec![0; campaign_account_size()])
+            };
+            let accounts = [writing.info(), creator.info(), registry.info()];
+            assert_eq!(create_campain(&program_id, &accounts, input_data), expected, "{}", image_link);
+        }
+    }
+
+    #[test]
+    fn rent_exemption_of_oversized_accounts_does_not_overflow() {
+        setup();
+        assert_eq!(rent_exemption(1_000), Ok(Rent::default().minimum_balance(1_000)));
+        assert_eq!(rent_exemption(usize::MAX), Err(ProgramError::InsufficientFunds));
+
+        /*
+         * an account far larger than the campaign needs a rent_exemption the lamports don't cover.
+         */
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let input_data = campaign(creator.key);
+        let (campaign_address, _) = campaign_pda(&program_id, &creator.key, &input_data.name);
+        let mut writing = TestAccount {
+            key: campaign_address,
+            ..TestAccount::new(&program_id, 1_000_000_000, vec![0; 10 * 1024 * 1024])
+        };
+        let accounts = [writing.info(), creator.info(), registry.info()];
+        assert_eq!(
+            create_campain(&program_id, &accounts, input_data),
+            Err(CrowdfundingError::InsufficientFundsForRent.into())
+        );
+    }
+
+    #[test]
+    fn campaign_account_size_fits_the_longest_campaign() {
+        let mut campaign_data = campaign(Pubkey::new_unique());
+        campaign_data.name = "n".repeat(MAX_NAME_LEN);
+        campaign_data.description = "d".repeat(MAX_DESCRIPTION_LEN);
+        campaign_data.image_link = "i".repeat(MAX_IMAGE_LINK_LEN);
+        assert_eq!(campaign_data.try_to_vec().unwrap().len(), campaign_account_size());
+    }
+
+    #[test]
+    fn create_campain_rejects_over_length_strings() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let mut too_long = [campaign(creator.key), campaign(creator.key), campaign(creator.key)];
+        too_long[0].name = "n".repeat(MAX_NAME_LEN + 1);
+        too_long[1].description = "d".repeat(MAX_DESCRIPTION_LEN + 1);
+        too_long[2].image_link = "i".repeat(MAX_IMAGE_LINK_LEN + 1);
+
+        for input_data in too_long {
+            let mut writing = TestAccount::new(&program_id, 1_000_000_000, vec![0; 1024]);
+            let accounts = [writing.info(), creator.info(), registry.info()];
+            assert_eq!(
+                create_campain(&program_id, &accounts, input_data),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        }
+    }
+
+    #[test]
+    fn create_campain_checks_the_signers() {
+        setup();
+        let program_id = Pubkey::new_unique();
+        let mut creator = TestAccount::signer(0);
+        let mut registry = admin_registry(&program_id, &creator.key);
+        let signer = Pubkey::new_unique();
+        let mut invalid = [campaign(creator.key), campaign(creator.key), campaign(creator.key)];
+        invalid[0].signers = vec![signer];
+        invalid[0].required_signers = 2;
+        invalid[1].signers = vec![signer, signer];
+        invalid[1].required_signers = 1;
+        invalid[2].signers = (0..=MAX_SIGNERS).map(|_| Pubkey::new_unique()).collect();
+
+        for input_data in invalid {
+            let mut writing = TestAccount::new(&program_id, 1_000_000_000, vec![0; 1024]);
+            let accounts = [writing.info(), creator.info(), registry.info()];
+            assert_eq!(
+                create_campain(&program_id, &accounts, input_data),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        }
     }
 }
 
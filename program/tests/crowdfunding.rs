@@ -0,0 +1,237 @@
+/*
+ * End to end tests: the program runs inside solana-program-test and every step
+ * goes through a real transaction sent with the BanksClient.
+ */
+use borsh::{BorshDeserialize, BorshSerialize};
+use program::{
+    admin_registry_pda, campaign_pda, global_state_pda, platform, process_instruction, AdminRegistry,
+    CampaignCategory, CampaignDetails, CampaignStatus, CampaignType, GlobalState, CAMPAIGN_VERSION,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction, system_program,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/*
+ * Variant indexes of the CrowdfundingInstruction enum.
+ */
+const CREATE_CAMPAIGN: u8 = 0;
+const WITHDRAW: u8 = 1;
+const DONATE: u8 = 2;
+
+const DONATION: u64 = 1_000_000_000;
+const WITHDRAWAL: u64 = 500_000_000;
+
+async fn get_account(banks_client: &mut BanksClient, address: Pubkey) -> Account {
+    banks_client
+    .get_account(address)
+    .await
+    .unwrap()
+    .expect("account not found")
+}
+
+async fn get_campaign(banks_client: &mut BanksClient, address: Pubkey) -> CampaignDetails {
+    let account = get_account(banks_client, address).await;
+    CampaignDetails::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+#[tokio::test]
+async fn create_donate_and_withdraw() {
+    let program_id = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) =
+        ProgramTest::new("program", program_id, processor!(process_instruction))
+        .start()
+        .await;
+    let beneficiary = Pubkey::new_unique();
+
+    /*
+     * create the campaign, the program allocates the campaign address and the payer is the admin.
+     */
+    let name = String::from("integration");
+    let (campaign_address, _) = campaign_pda(&program_id, &payer.pubkey(), &name);
+    let (registry_address, _) = admin_registry_pda(&program_id, &payer.pubkey());
+    let (global_address, _) = global_state_pda(&program_id);
+    let details = CampaignDetails {
+        version: CAMPAIGN_VERSION,
+        admin: payer.pubkey(),
+        name,
+        description: String::from("description"),
+        image_link: String::from("https://example.com/image.png"),
+        amount_donated: 0,
+        target_amount: 10 * DONATION,
+        deadline: 0,
+        donor_count: 0,
+        min_donation: 0,
+        status: CampaignStatus::Active as u8,
+        category: CampaignCategory::Community as u8,
+        beneficiary,
+        campaign_type: CampaignType::Flexible as u8,
+        last_donor: Pubkey::default(),
+        mint: Pubkey::default(),
+        token_amount_donated: 0,
+        matcher: Pubkey::default(),
+        match_pool_remaining: 0,
+        max_withdraw_per_day: 0,
+        withdrawn_today: 0,
+        last_withdraw_ts: 0,
+        created_at: 0,
+        largest_donation: 0,
+        largest_donor: Pubkey::default(),
+        pool_fee_bps: 0,
+        next_withdraw_ts: 0,
+        interval_seconds: 0,
+        per_interval_amount: 0,
+        metadata_account: Pubkey::default(),
+        refunds_enabled: false,
+        required_signers: 0,
+        signers: Vec::new(),
+        cap_at_goal: false,
+        block_self_donation: false,
+        pending_withdraw_amount: 0,
+        withdraw_unlock_ts: 0,
+        max_donation_per_donor: 0,
+        goal_reached_at: 0,
+        rent_reserved: 0,
+        timelock_threshold: 0,
+        seed_admin: Pubkey::default(),
+        seed_name: String::new(),
+        bump: 0,
+    };
+    let mut data = vec![CREATE_CAMPAIGN];
+    data.extend(details.try_to_vec().unwrap());
+    let mut transaction = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(campaign_address, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(registry_address, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new(global_address, false),
+            ],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = get_account(&mut banks_client, campaign_address).await;
+    let rent_exemption = account.lamports;
+    assert_eq!(account.owner, program_id);
+    let campaign = get_campaign(&mut banks_client, campaign_address).await;
+    assert_eq!(campaign.admin, payer.pubkey());
+    assert_eq!(campaign.amount_donated, 0);
+    assert_eq!(campaign.donor_count, 0);
+    assert_ne!(campaign.created_at, 0);
+    let registry = get_account(&mut banks_client, registry_address).await;
+    assert_eq!(AdminRegistry::try_from_slice(&registry.data).unwrap().campaign_count, 1);
+    let global = get_account(&mut banks_client, global_address).await;
+    assert_eq!(GlobalState::try_from_slice(&global.data).unwrap().total_campaigns, 1);
+
+    /*
+     * donate, the lamports are put in a program owned donator_program_account first.
+     */
+    let donator_program = Keypair::new();
+    let mut data = vec![DONATE];
+    data.extend(payer.pubkey().to_bytes());
+    /*
+     * not anonymous, so the payer is recorded as last_donor.
+     */
+    data.push(0);
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &donator_program.pubkey(),
+                DONATION,
+                0,
+                &program_id,
+            ),
+            Instruction::new_with_bytes(
+                program_id,
+                &data,
+                vec![
+                    AccountMeta::new(campaign_address, false),
+                    AccountMeta::new(donator_program.pubkey(), false),
+                    AccountMeta::new_readonly(payer.pubkey(), true),
+                ],
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &donator_program], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = get_account(&mut banks_client, campaign_address).await;
+    assert_eq!(account.lamports, rent_exemption + DONATION);
+    let campaign = get_campaign(&mut banks_client, campaign_address).await;
+    assert_eq!(campaign.amount_donated, DONATION);
+    assert_eq!(campaign.donor_count, 1);
+    assert_eq!(campaign.last_donor, payer.pubkey());
+    assert!(banks_client
+        .get_account(donator_program.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+
+    /*
+     * withdraw, the platform gets the fee and the beneficiary the rest.
+     */
+    let mut data = vec![WITHDRAW];
+    data.extend(WITHDRAWAL.to_le_bytes());
+    let mut transaction = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(campaign_address, false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new(platform::id(), false),
+                AccountMeta::new(beneficiary, false),
+            ],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let fee = WITHDRAWAL / 100;
+    let account = get_account(&mut banks_client, campaign_address).await;
+    assert_eq!(account.lamports, rent_exemption + DONATION - WITHDRAWAL);
+    assert_eq!(get_account(&mut banks_client, platform::id()).await.lamports, fee);
+    assert_eq!(get_account(&mut banks_client, beneficiary).await.lamports, WITHDRAWAL - fee);
+    let campaign = get_campaign(&mut banks_client, campaign_address).await;
+    assert_eq!(campaign.amount_donated, DONATION);
+    assert_eq!(campaign.withdrawn_today, WITHDRAWAL);
+
+    /*
+     * only the lamports left above the rent exemption can be withdrawn.
+     */
+    let mut data = vec![WITHDRAW];
+    data.extend((DONATION - WITHDRAWAL + 1).to_le_bytes());
+    let mut transaction = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(campaign_address, false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new(platform::id(), false),
+                AccountMeta::new(beneficiary, false),
+            ],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+    let account = get_account(&mut banks_client, campaign_address).await;
+    assert_eq!(account.lamports, rent_exemption + DONATION - WITHDRAWAL);
+}